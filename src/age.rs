@@ -0,0 +1,213 @@
+// This file is part of Qatsi.
+//
+// Copyright (c) 2025  René Coignard <contact@renecoignard.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+const IDENTITY_HRP: &str = "age-secret-key-";
+const RECIPIENT_HRP: &str = "age";
+
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+pub struct AgeKeyPair {
+    pub identity: Zeroizing<String>,
+    pub recipient: String,
+}
+
+pub struct AgeIdentityFile {
+    pub contents: Zeroizing<String>,
+    pub recipient: String,
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    let mut checksum = 1u32;
+    for &value in values {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x1ffffff) << 5) ^ value as u32;
+        for (i, generator) in BECH32_GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+fn to_base32(data: &[u8]) -> Zeroizing<Vec<u8>> {
+    let mut values = Zeroizing::new(Vec::with_capacity((data.len() * 8).div_ceil(5)));
+    let mut accumulator = 0u32;
+    let mut bits = 0u32;
+
+    for &byte in data {
+        accumulator = (accumulator << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            values.push(((accumulator >> bits) & 31) as u8);
+        }
+    }
+    if bits > 0 {
+        values.push(((accumulator << (5 - bits)) & 31) as u8);
+    }
+
+    values
+}
+
+fn bech32_encode_values(hrp: &str, values: &[u8]) -> Zeroizing<String> {
+    let mut checksum_input = Zeroizing::new(Vec::with_capacity(hrp.len() * 2 + values.len() + 7));
+    checksum_input.extend(hrp.bytes().map(|c| c >> 5));
+    checksum_input.push(0);
+    checksum_input.extend(hrp.bytes().map(|c| c & 31));
+    checksum_input.extend_from_slice(values);
+    checksum_input.extend_from_slice(&[0; 6]);
+    let polymod = bech32_polymod(&checksum_input) ^ 1;
+
+    let mut encoded = Zeroizing::new(String::with_capacity(hrp.len() + values.len() + 7));
+    encoded.push_str(hrp);
+    encoded.push('1');
+    for &value in values {
+        encoded.push(BECH32_CHARSET[value as usize] as char);
+    }
+    for i in 0..6 {
+        encoded.push(BECH32_CHARSET[((polymod >> (5 * (5 - i))) & 31) as usize] as char);
+    }
+
+    encoded
+}
+
+// BIP-173 Bech32 (not Bech32m), as used by age. The 90-character length
+// limit from BIP-173 is not enforced, matching the age specification.
+fn bech32_encode(hrp: &str, data: &[u8]) -> Zeroizing<String> {
+    bech32_encode_values(hrp, &to_base32(data))
+}
+
+pub fn generate_age_keypair(key: &[u8; 32]) -> AgeKeyPair {
+    let secret = StaticSecret::from(*key);
+    let public = PublicKey::from(&secret);
+
+    let identity = Zeroizing::new(bech32_encode(IDENTITY_HRP, key).to_uppercase());
+    let recipient = bech32_encode(RECIPIENT_HRP, public.as_bytes()).to_string();
+
+    AgeKeyPair {
+        identity,
+        recipient,
+    }
+}
+
+// Same layout as age-keygen, so the output can be saved as an identity file.
+pub fn generate_age_identity(key: &[u8; 32]) -> AgeIdentityFile {
+    let pair = generate_age_keypair(key);
+    AgeIdentityFile {
+        contents: Zeroizing::new(format!(
+            "# public key: {}\n{}",
+            pair.recipient, &*pair.identity
+        )),
+        recipient: pair.recipient,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_hex(s: &str) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).unwrap();
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_bech32_bip173_vectors() {
+        assert_eq!(*bech32_encode_values("a", &[]), "a12uel5l");
+
+        let values: Vec<u8> = (0..32).collect();
+        assert_eq!(
+            *bech32_encode_values("abcdef", &values),
+            "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw"
+        );
+
+        // BIP-173 P2WPKH example: witness version 0 followed by the program
+        let hash = [
+            0x75, 0x1e, 0x76, 0xe8, 0x19, 0x91, 0x96, 0xd4, 0x54, 0x94, 0x1c, 0x45, 0xd1, 0xb3,
+            0xa3, 0x23, 0xf1, 0x43, 0x3b, 0xd6,
+        ];
+        let mut program = vec![0u8];
+        program.extend_from_slice(&to_base32(&hash));
+        assert_eq!(
+            *bech32_encode_values("bc", &program),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        );
+    }
+
+    #[test]
+    fn test_bech32_encode_bytes() {
+        let encoded = bech32_encode("abcdef", &[0x00, 0x44, 0x32, 0x14, 0xc7]);
+        assert_eq!(
+            *encoded,
+            *bech32_encode_values("abcdef", &[0, 1, 2, 3, 4, 5, 6, 7])
+        );
+    }
+
+    #[test]
+    fn test_rfc7748_public_key() {
+        // RFC 7748 section 6.1
+        let alice = from_hex("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let expected = from_hex("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a");
+
+        let pair = generate_age_keypair(&alice);
+        assert_eq!(
+            pair.recipient,
+            *bech32_encode(RECIPIENT_HRP, &expected),
+            "recipient must encode the X25519 public key"
+        );
+    }
+
+    #[test]
+    fn test_age_known_answer() {
+        let key = from_hex("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let pair = generate_age_keypair(&key);
+
+        assert_eq!(
+            pair.recipient,
+            "age1s5s0qzvfxzn4gayt0hwtg0hhtgxm7wsdycup4a8t5j5ca25mfe4qt4hs7q"
+        );
+        assert_eq!(
+            *pair.identity,
+            "AGE-SECRET-KEY-1WURK6ZNNRZJH60QKC9E9RVNXGH05CTU8A0QFJ243WLA628DE9S4QRFH26J"
+        );
+    }
+
+    #[test]
+    fn test_identity_format() {
+        let identity = generate_age_identity(&[42u8; 32]);
+        let lines: Vec<&str> = identity.contents.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("# public key: age1"));
+        assert_eq!(lines[0].len(), "# public key: ".len() + 62);
+        assert!(lines[1].starts_with("AGE-SECRET-KEY-1"));
+        assert_eq!(lines[1].len(), 74);
+        assert_eq!(lines[1], lines[1].to_uppercase());
+        assert_eq!(
+            identity.recipient,
+            generate_age_keypair(&[42u8; 32]).recipient
+        );
+    }
+}
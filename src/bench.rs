@@ -0,0 +1,197 @@
+// This file is part of Qatsi.
+//
+// Copyright (c) 2025  René Coignard <contact@renecoignard.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::kdf::{Argon2Config, derive_hierarchical};
+use anyhow::{Context, Result};
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
+
+pub const DEFAULT_MEMORY_MIB: &[u32] = &[32, 64, 128, 256];
+pub const DEFAULT_ITERATIONS: &[u32] = &[8, 16, 32];
+pub const DEFAULT_PARALLELISM: &[u32] = &[4, 6, 8];
+
+const BENCH_MASTER: &[u8] = b"qatsi-benchmark-master-secret";
+const BENCH_LAYER: &str = "qatsi-benchmark-layer";
+
+#[derive(Debug, Clone, Copy)]
+pub struct BenchSample {
+    pub config: Argon2Config,
+    pub per_layer: Duration,
+}
+
+impl BenchSample {
+    pub fn total(&self, layers: usize) -> Duration {
+        self.per_layer * layers as u32
+    }
+}
+
+pub fn cost(config: &Argon2Config) -> u64 {
+    config.memory_kib as u64 * config.iterations as u64
+}
+
+pub fn meets_floor(config: &Argon2Config, floor: &Argon2Config) -> bool {
+    config.memory_kib >= floor.memory_kib
+        && config.iterations >= floor.iterations
+        && config.parallelism >= floor.parallelism
+}
+
+// `accept` decides which configurations are worth measuring, so the grid
+// follows the same rule as the KDF check shown after a derivation.
+pub fn grid(
+    memory_mib: &[u32],
+    iterations: &[u32],
+    parallelism: &[u32],
+    accept: impl Fn(&Argon2Config) -> bool,
+) -> Result<Vec<Argon2Config>> {
+    let memory_kib = memory_mib
+        .iter()
+        .map(|&m| {
+            m.checked_mul(1024)
+                .with_context(|| format!("Memory cost {} MiB is too large", m))
+        })
+        .collect::<Result<Vec<u32>>>()?;
+
+    let mut configs: Vec<Argon2Config> = parallelism
+        .iter()
+        .flat_map(|&p| {
+            memory_kib.iter().flat_map(move |&m| {
+                iterations.iter().map(move |&t| Argon2Config {
+                    memory_kib: m,
+                    iterations: t,
+                    parallelism: p,
+                })
+            })
+        })
+        .filter(|config| accept(config))
+        .collect();
+
+    configs.sort_by_key(|config| (config.parallelism, cost(config), config.memory_kib));
+    configs.dedup_by_key(|config| (config.parallelism, config.memory_kib, config.iterations));
+    Ok(configs)
+}
+
+pub fn measure_layer(config: Argon2Config) -> Result<Duration> {
+    let layers = vec![Zeroizing::new(BENCH_LAYER.to_string())];
+
+    let start = Instant::now();
+    derive_hierarchical(BENCH_MASTER, &layers, config)?;
+    Ok(start.elapsed())
+}
+
+pub fn recommend(samples: &[BenchSample], layers: usize, target: Duration) -> Option<BenchSample> {
+    samples
+        .iter()
+        .filter(|sample| sample.total(layers) <= target)
+        .max_by(|a, b| {
+            cost(&a.config)
+                .cmp(&cost(&b.config))
+                .then(a.config.memory_kib.cmp(&b.config.memory_kib))
+                .then(b.per_layer.cmp(&a.per_layer))
+        })
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(memory_mib: u32, iterations: u32, parallelism: u32) -> Argon2Config {
+        Argon2Config {
+            memory_kib: memory_mib * 1024,
+            iterations,
+            parallelism,
+        }
+    }
+
+    #[test]
+    fn test_grid_respects_floor() {
+        let floor = config(64, 16, 4);
+        let configs = grid(&[32, 64, 128], &[8, 16, 32], &[2, 4], |c| {
+            meets_floor(c, &floor)
+        })
+        .unwrap();
+
+        assert_eq!(configs.len(), 4);
+        for c in &configs {
+            assert!(c.memory_mib() >= 64);
+            assert!(c.iterations >= 16);
+            assert_eq!(c.parallelism, 4);
+        }
+    }
+
+    #[test]
+    fn test_grid_sorted_by_cost() {
+        let floor = config(32, 8, 4);
+        let configs = grid(&[128, 32, 64], &[16, 8], &[6, 4], |c| {
+            meets_floor(c, &floor)
+        })
+        .unwrap();
+
+        assert_eq!(configs.len(), 12);
+        for pair in configs.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            assert!(a.parallelism <= b.parallelism);
+            if a.parallelism == b.parallelism {
+                assert!(cost(a) <= cost(b));
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid_rejects_oversized_memory() {
+        assert!(grid(&[u32::MAX], &[8], &[4], |_| true).is_err());
+        assert!(grid(&[4 * 1024 * 1024], &[8], &[4], |_| true).is_err());
+    }
+
+    #[test]
+    fn test_recommend_highest_cost_within_target() {
+        let samples = vec![
+            BenchSample {
+                config: config(32, 8, 4),
+                per_layer: Duration::from_millis(100),
+            },
+            BenchSample {
+                config: config(64, 16, 4),
+                per_layer: Duration::from_millis(400),
+            },
+            BenchSample {
+                config: config(128, 8, 4),
+                per_layer: Duration::from_millis(450),
+            },
+            BenchSample {
+                config: config(128, 16, 4),
+                per_layer: Duration::from_millis(800),
+            },
+        ];
+
+        let best = recommend(&samples, 3, Duration::from_millis(1500)).unwrap();
+        assert_eq!(best.config.memory_mib(), 128);
+        assert_eq!(best.config.iterations, 8);
+
+        let best = recommend(&samples, 1, Duration::from_millis(1000)).unwrap();
+        assert_eq!(best.config.memory_mib(), 128);
+        assert_eq!(best.config.iterations, 16);
+
+        assert!(recommend(&samples, 3, Duration::from_millis(200)).is_none());
+    }
+
+    #[test]
+    fn test_measure_layer() {
+        let elapsed = measure_layer(config(1, 1, 1)).unwrap();
+        assert!(elapsed > Duration::ZERO);
+    }
+}
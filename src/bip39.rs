@@ -0,0 +1,262 @@
+// This file is part of Qatsi.
+//
+// Copyright (c) 2025  René Coignard <contact@renecoignard.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+use zeroize::Zeroizing;
+
+const WORDLIST_DATA: &str = include_str!("../assets/bip39_english_wordlist.txt");
+
+#[cfg(test)]
+const EXPECTED_SHA256: &str = "2f5eed53a4727b4bf8880d8f3f199efc90e58503646d9ff8eff3a2ed3b24dbda";
+
+pub const VALID_WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+
+const BITS_PER_WORD: usize = 11;
+
+static WORDLIST: OnceLock<Vec<&'static str>> = OnceLock::new();
+
+pub fn get_bip39_wordlist() -> &'static [&'static str] {
+    WORDLIST.get_or_init(|| {
+        let words: Vec<&'static str> = WORDLIST_DATA
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        assert_eq!(
+            words.len(),
+            2048,
+            "BIP39 wordlist must contain exactly 2048 words"
+        );
+        words
+    })
+}
+
+pub const fn bip39_wordlist_size() -> u16 {
+    2048
+}
+
+pub fn bip39_entropy(word_count: usize) -> f64 {
+    (word_count * BITS_PER_WORD * 32 / 33) as f64
+}
+
+pub fn check_word_count(word_count: usize) -> Result<()> {
+    if !VALID_WORD_COUNTS.contains(&word_count) {
+        anyhow::bail!(
+            "BIP39 word count must be 12, 15, 18, 21 or 24, got {}",
+            word_count
+        );
+    }
+    Ok(())
+}
+
+fn checksum_byte(entropy: &[u8]) -> u8 {
+    let mut hasher = Sha256::new();
+    hasher.update(entropy);
+    hasher.finalize()[0]
+}
+
+pub fn entropy_to_mnemonic(entropy: &[u8]) -> Result<Zeroizing<String>> {
+    if !matches!(entropy.len(), 16 | 20 | 24 | 28 | 32) {
+        anyhow::bail!(
+            "BIP39 entropy must be 16, 20, 24, 28 or 32 bytes, got {}",
+            entropy.len()
+        );
+    }
+
+    let wordlist = get_bip39_wordlist();
+    let word_count = entropy.len() * 8 * 33 / 32 / BITS_PER_WORD;
+
+    let mut bits = Zeroizing::new(entropy.to_vec());
+    bits.push(checksum_byte(entropy));
+
+    let words: Vec<&str> = (0..word_count)
+        .map(|i| {
+            let index = (0..BITS_PER_WORD).fold(0usize, |acc, b| {
+                let bit = i * BITS_PER_WORD + b;
+                (acc << 1) | ((bits[bit / 8] >> (7 - bit % 8)) & 1) as usize
+            });
+            wordlist[index]
+        })
+        .collect();
+
+    Ok(Zeroizing::new(words.join(" ")))
+}
+
+pub fn mnemonic_to_entropy(phrase: &str) -> Result<Zeroizing<Vec<u8>>> {
+    let wordlist = get_bip39_wordlist();
+    let words: Vec<&str> = phrase.split_whitespace().collect();
+    check_word_count(words.len())?;
+
+    let mut bits = Zeroizing::new(vec![0u8; (words.len() * BITS_PER_WORD).div_ceil(8)]);
+
+    for (i, word) in words.iter().enumerate() {
+        let index = wordlist
+            .binary_search(&word.to_lowercase().as_str())
+            // The word itself is part of a secret, so only its position is reported
+            .map_err(|_| anyhow::anyhow!("Word {} is not in the BIP39 list", i + 1))?;
+
+        for b in 0..BITS_PER_WORD {
+            if (index >> (BITS_PER_WORD - 1 - b)) & 1 == 1 {
+                let bit = i * BITS_PER_WORD + b;
+                bits[bit / 8] |= 1 << (7 - bit % 8);
+            }
+        }
+    }
+
+    let entropy_len = words.len() * BITS_PER_WORD * 32 / 33 / 8;
+    let checksum_bits = words.len() * BITS_PER_WORD / 33;
+    let mask = (0xff00u16 >> checksum_bits) as u8;
+
+    let entropy = Zeroizing::new(bits[..entropy_len].to_vec());
+    if (checksum_byte(&entropy) ^ bits[entropy_len]) & mask != 0 {
+        anyhow::bail!("Invalid BIP39 checksum");
+    }
+
+    Ok(entropy)
+}
+
+pub fn generate_bip39(key: &[u8; 32], word_count: usize) -> Result<Zeroizing<String>> {
+    check_word_count(word_count)?;
+
+    let entropy = &key[..word_count * 4 / 3];
+    let mnemonic = entropy_to_mnemonic(entropy)?;
+
+    if *mnemonic_to_entropy(&mnemonic)? != entropy {
+        anyhow::bail!("BIP39 round-trip verification failed");
+    }
+
+    Ok(mnemonic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_bip39_wordlist_sha256() {
+        let mut hasher = Sha256::new();
+        hasher.update(WORDLIST_DATA.as_bytes());
+        let result = format!("{:x}", hasher.finalize());
+
+        assert_eq!(
+            result, EXPECTED_SHA256,
+            "BIP39 wordlist SHA-256 mismatch; file may be corrupted"
+        );
+    }
+
+    #[test]
+    fn test_bip39_wordlist_integrity() {
+        let words = get_bip39_wordlist();
+
+        assert_eq!(words.len(), bip39_wordlist_size() as usize);
+        assert_eq!(words[0], "abandon");
+        assert_eq!(words[2047], "zoo");
+        assert!(
+            words.windows(2).all(|pair| pair[0] < pair[1]),
+            "BIP39 wordlist must be sorted and unique"
+        );
+    }
+
+    #[test]
+    fn test_bip39_reference_vectors() {
+        let vectors = vec![
+            (
+                "00000000000000000000000000000000",
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            ),
+            (
+                "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+                "legal winner thank year wave sausage worth useful legal winner thank yellow",
+            ),
+            (
+                "80808080808080808080808080808080",
+                "letter advice cage absurd amount doctor acoustic avoid letter advice cage above",
+            ),
+            (
+                "ffffffffffffffffffffffffffffffff",
+                "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong",
+            ),
+            (
+                "000000000000000000000000000000000000000000000000",
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon agent",
+            ),
+            (
+                "9e885d952ad362caeb4efe34a8e91bd2",
+                "ozone drill grab fiber curtain grace pudding thank cruise elder eight picnic",
+            ),
+            (
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art",
+            ),
+            (
+                "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+                "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo vote",
+            ),
+        ];
+
+        for (entropy_hex, expected) in vectors {
+            let entropy = from_hex(entropy_hex);
+            let mnemonic = entropy_to_mnemonic(&entropy).unwrap();
+            assert_eq!(*mnemonic, expected);
+
+            let decoded = mnemonic_to_entropy(expected).unwrap();
+            assert_eq!(*decoded, entropy);
+        }
+    }
+
+    #[test]
+    fn test_bip39_round_trip_from_key() {
+        let key = [42u8; 32];
+
+        for word_count in VALID_WORD_COUNTS {
+            let mnemonic = generate_bip39(&key, word_count).unwrap();
+            assert_eq!(mnemonic.split(' ').count(), word_count);
+
+            let entropy = mnemonic_to_entropy(&mnemonic).unwrap();
+            assert_eq!(*entropy, key[..word_count * 4 / 3]);
+            assert_eq!(bip39_entropy(word_count), (entropy.len() * 8) as f64);
+        }
+    }
+
+    #[test]
+    fn test_bip39_invalid_input() {
+        let key = [42u8; 32];
+        assert!(generate_bip39(&key, 8).is_err());
+        assert!(generate_bip39(&key, 13).is_err());
+        assert!(entropy_to_mnemonic(&[0u8; 15]).is_err());
+
+        let bad_checksum = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon";
+        assert!(mnemonic_to_entropy(bad_checksum).is_err());
+
+        let unknown_word = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon qatsi";
+        let err = mnemonic_to_entropy(unknown_word).unwrap_err().to_string();
+        assert!(!err.contains("qatsi"));
+        assert!(err.contains("12"));
+
+        assert!(mnemonic_to_entropy("abandon about").is_err());
+    }
+}
@@ -0,0 +1,354 @@
+// This file is part of Qatsi.
+//
+// Copyright (c) 2025  René Coignard <contact@renecoignard.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::verify::constant_time_eq;
+use anyhow::{Context, Result};
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use zeroize::Zeroizing;
+
+pub const DEFAULT_CLEAR_SECONDS: u64 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Restored {
+    Previous,
+    Cleared,
+    // Something else was copied meanwhile and is left alone
+    Replaced,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clipboard {
+    copy: Vec<String>,
+    paste: Option<Vec<String>>,
+    clear: Option<Vec<String>>,
+    // Extra copy arguments asking clipboard managers not to keep the secret
+    sensitive: Vec<String>,
+}
+
+struct Tool {
+    program: &'static str,
+    copy: &'static str,
+    paste: Option<&'static str>,
+    clear: Option<&'static str>,
+    sensitive: &'static str,
+}
+
+const MACOS_TOOLS: &[Tool] = &[Tool {
+    program: "pbcopy",
+    copy: "pbcopy",
+    paste: Some("pbpaste"),
+    clear: None,
+    sensitive: "",
+}];
+
+const WINDOWS_TOOLS: &[Tool] = &[Tool {
+    program: "clip.exe",
+    copy: "clip.exe",
+    paste: None,
+    clear: None,
+    sensitive: "",
+}];
+
+// wl-copy marks the offer with x-kde-passwordManagerHint; xclip and xsel
+// can only offer a single target, so there is nothing to mark on X11.
+const UNIX_TOOLS: &[Tool] = &[
+    Tool {
+        program: "wl-copy",
+        copy: "wl-copy",
+        paste: Some("wl-paste --no-newline"),
+        clear: Some("wl-copy --clear"),
+        sensitive: "--sensitive",
+    },
+    Tool {
+        program: "xclip",
+        copy: "xclip -selection clipboard -in",
+        paste: Some("xclip -selection clipboard -out"),
+        clear: None,
+        sensitive: "",
+    },
+    Tool {
+        program: "xsel",
+        copy: "xsel --clipboard --input",
+        paste: Some("xsel --clipboard --output"),
+        clear: Some("xsel --clipboard --clear"),
+        sensitive: "",
+    },
+];
+
+fn split_command(command: &str) -> Result<Vec<String>> {
+    let args: Vec<String> = command.split_whitespace().map(str::to_string).collect();
+    if args.is_empty() {
+        anyhow::bail!("Clipboard command cannot be empty");
+    }
+    Ok(args)
+}
+
+fn find_in_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
+
+fn is_set(var: &str) -> bool {
+    std::env::var_os(var).is_some_and(|value| !value.is_empty())
+}
+
+impl Clipboard {
+    pub fn from_commands(copy: &str, paste: Option<&str>) -> Result<Self> {
+        Ok(Self {
+            copy: split_command(copy)?,
+            paste: paste.map(split_command).transpose()?,
+            clear: None,
+            sensitive: Vec::new(),
+        })
+    }
+
+    fn from_tool(tool: &Tool) -> Result<Self> {
+        Ok(Self {
+            clear: tool.clear.map(split_command).transpose()?,
+            sensitive: tool
+                .sensitive
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            ..Self::from_commands(tool.copy, tool.paste)?
+        })
+    }
+
+    // Wayland and X11 selections are owned by a process that keeps serving
+    // them, so the usual helper tools are used rather than the protocols.
+    pub fn detect() -> Result<Self> {
+        let candidates = if cfg!(target_os = "macos") {
+            MACOS_TOOLS
+        } else if cfg!(windows) {
+            WINDOWS_TOOLS
+        } else {
+            UNIX_TOOLS
+        };
+
+        let wayland = is_set("WAYLAND_DISPLAY");
+        let x11 = is_set("DISPLAY");
+
+        for tool in candidates {
+            let session = match tool.program {
+                "wl-copy" => wayland,
+                "xclip" | "xsel" => x11,
+                _ => true,
+            };
+            if session && find_in_path(tool.program) {
+                return Self::from_tool(tool);
+            }
+        }
+
+        anyhow::bail!("No clipboard tool found; pass --clip-command (and --clip-paste-command)")
+    }
+
+    pub fn program(&self) -> &str {
+        &self.copy[0]
+    }
+
+    pub fn can_restore(&self) -> bool {
+        self.paste.is_some()
+    }
+
+    pub fn copy(&self, contents: &[u8]) -> Result<()> {
+        self.run_copy(&[], contents)
+    }
+
+    pub fn copy_secret(&self, secret: &[u8]) -> Result<()> {
+        self.run_copy(&self.sensitive, secret)
+    }
+
+    fn run_copy(&self, extra: &[String], contents: &[u8]) -> Result<()> {
+        let mut child = Command::new(&self.copy[0])
+            .args(&self.copy[1..])
+            .args(extra)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("Failed to run {}", self.program()))?;
+
+        let mut stdin = child
+            .stdin
+            .take()
+            .context("Failed to open clipboard input")?;
+        stdin
+            .write_all(contents)
+            .with_context(|| format!("Failed to write to {}", self.program()))?;
+        drop(stdin);
+
+        let status = child.wait()?;
+        if !status.success() {
+            anyhow::bail!("{} exited with {}", self.program(), status);
+        }
+        Ok(())
+    }
+
+    // Tools with a clear option drop the selection instead of offering an
+    // empty one; the others are given empty input.
+    pub fn clear(&self) -> Result<()> {
+        let Some(clear) = &self.clear else {
+            return self.copy(&[]);
+        };
+
+        let status = Command::new(&clear[0])
+            .args(&clear[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .with_context(|| format!("Failed to run {}", clear[0]))?;
+        if !status.success() {
+            anyhow::bail!("{} exited with {}", clear[0], status);
+        }
+        Ok(())
+    }
+
+    // None when the contents cannot be read back; an empty or non-text
+    // clipboard makes most paste tools fail.
+    pub fn paste(&self) -> Option<Zeroizing<Vec<u8>>> {
+        let paste = self.paste.as_ref()?;
+        let mut child = Command::new(&paste[0])
+            .args(&paste[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+
+        let mut contents = Zeroizing::new(Vec::new());
+        child.stdout.take()?.read_to_end(&mut contents).ok()?;
+        child.wait().ok()?.success().then_some(contents)
+    }
+
+    pub fn restore(
+        &self,
+        secret: &[u8],
+        previous: Option<&Zeroizing<Vec<u8>>>,
+    ) -> Result<Restored> {
+        if self.can_restore() {
+            let current = self.paste();
+            let still_secret = current
+                .as_ref()
+                .is_some_and(|current| constant_time_eq(current, secret));
+            if !still_secret {
+                return Ok(Restored::Replaced);
+            }
+        }
+
+        match previous {
+            Some(previous) if !previous.is_empty() => {
+                self.copy(previous)?;
+                Ok(Restored::Previous)
+            }
+            _ => {
+                self.clear()?;
+                Ok(Restored::Cleared)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_command() {
+        assert_eq!(
+            split_command("  xclip -selection clipboard ").unwrap(),
+            ["xclip", "-selection", "clipboard"]
+        );
+        assert!(split_command("").is_err());
+        assert!(Clipboard::from_commands(" ", None).is_err());
+        assert!(
+            !Clipboard::from_commands("clip.exe", None)
+                .unwrap()
+                .can_restore()
+        );
+    }
+
+    #[test]
+    fn test_tool_options() {
+        let wayland = Clipboard::from_tool(&UNIX_TOOLS[0]).unwrap();
+        assert_eq!(wayland.program(), "wl-copy");
+        assert_eq!(wayland.sensitive, ["--sensitive"]);
+        assert_eq!(wayland.clear.as_deref().unwrap(), ["wl-copy", "--clear"]);
+
+        let xclip = Clipboard::from_tool(&UNIX_TOOLS[1]).unwrap();
+        assert!(xclip.sensitive.is_empty());
+        assert!(xclip.clear.is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_and_restore() {
+        let dir = std::env::temp_dir().join(format!("qatsi-clipboard-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("clipboard");
+        let path = file.display();
+
+        let clipboard = Clipboard::from_commands(
+            &format!("cp /dev/stdin {}", path),
+            Some(&format!("cat {}", path)),
+        )
+        .unwrap();
+
+        clipboard.copy(b"previous").unwrap();
+        let previous = clipboard.paste();
+        assert_eq!(
+            previous.as_deref().map(Vec::as_slice),
+            Some(&b"previous"[..])
+        );
+
+        clipboard.copy_secret(b"secret").unwrap();
+        assert_eq!(
+            clipboard.restore(b"secret", previous.as_ref()).unwrap(),
+            Restored::Previous
+        );
+        assert_eq!(std::fs::read(&file).unwrap(), b"previous");
+
+        clipboard.copy(b"secret").unwrap();
+        clipboard.copy(b"copied later").unwrap();
+        assert_eq!(
+            clipboard.restore(b"secret", previous.as_ref()).unwrap(),
+            Restored::Replaced
+        );
+        assert_eq!(std::fs::read(&file).unwrap(), b"copied later");
+
+        clipboard.copy(b"secret").unwrap();
+        assert_eq!(
+            clipboard.restore(b"secret", None).unwrap(),
+            Restored::Cleared
+        );
+        assert!(std::fs::read(&file).unwrap().is_empty());
+
+        let with_clear = Clipboard {
+            clear: Some(vec!["rm".to_string(), file.display().to_string()]),
+            ..clipboard
+        };
+        with_clear.copy_secret(b"secret").unwrap();
+        assert_eq!(
+            with_clear.restore(b"secret", None).unwrap(),
+            Restored::Cleared
+        );
+        assert!(!file.exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
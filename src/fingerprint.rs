@@ -0,0 +1,120 @@
+// This file is part of Qatsi.
+//
+// Copyright (c) 2025  René Coignard <contact@renecoignard.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::kdf::Argon2Config;
+use crate::wordlist::get_wordlist;
+use anyhow::{Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use zeroize::Zeroizing;
+
+// A fixed salt keeps the fingerprint apart from every derivation, whose
+// first salt is always a layer.
+const FINGERPRINT_DOMAIN: &[u8] = b"qatsi-master-fingerprint-v1";
+
+// The fingerprint depends on the master alone, so a seen fingerprint must
+// cost at least as much per guess as a Standard derivation. The cost is
+// fixed so the words stay the same across security levels.
+const FINGERPRINT_CONFIG: Argon2Config = Argon2Config::STANDARD;
+
+pub const FINGERPRINT_WORDS: usize = 3;
+
+// Three EFF words (38.8 bits): enough to notice a typo, too few to identify
+// the master.
+pub fn master_fingerprint(master_secret: &[u8]) -> Result<String> {
+    fingerprint_with_config(master_secret, &FINGERPRINT_CONFIG)
+}
+
+fn fingerprint_with_config(master_secret: &[u8], config: &Argon2Config) -> Result<String> {
+    let params = Params::new(
+        config.memory_kib,
+        config.iterations,
+        config.parallelism,
+        Some(8),
+    )
+    .context("Failed to create Argon2 parameters")?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+    let mut digest = Zeroizing::new([0u8; 8]);
+    argon2
+        .hash_password_into(master_secret, FINGERPRINT_DOMAIN, &mut *digest)
+        .map_err(|e| anyhow::anyhow!("Argon2 fingerprint failed: {:?}", e))?;
+
+    let wordlist = get_wordlist();
+    let mut value = u64::from_le_bytes(*digest);
+    let words: Vec<&str> = (0..FINGERPRINT_WORDS)
+        .map(|_| {
+            let word = wordlist[(value % wordlist.len() as u64) as usize];
+            value /= wordlist.len() as u64;
+            word
+        })
+        .collect();
+
+    Ok(words.join("-"))
+}
+
+// Accepts the words in any case, separated by hyphens or whitespace.
+pub fn fingerprint_matches(fingerprint: &str, expected: &str) -> bool {
+    let expected: Vec<String> = expected
+        .split(|c: char| c == '-' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    expected.join("-") == fingerprint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAST_CONFIG: Argon2Config = Argon2Config {
+        memory_kib: 8 * 1024,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    #[test]
+    fn test_fingerprint_format() {
+        let fingerprint = master_fingerprint(b"correct horse battery staple").unwrap();
+        let words: Vec<&str> = fingerprint.split('-').collect();
+
+        assert_eq!(words.len(), FINGERPRINT_WORDS);
+        assert!(words.iter().all(|word| get_wordlist().contains(word)));
+        assert_eq!(fingerprint, "unified-joystick-playmaker");
+    }
+
+    #[test]
+    fn test_fingerprint_detects_typos() {
+        let fingerprint =
+            fingerprint_with_config(b"correct horse battery staple", &FAST_CONFIG).unwrap();
+        assert_ne!(
+            fingerprint,
+            fingerprint_with_config(b"correct horse battery stapel", &FAST_CONFIG).unwrap()
+        );
+        assert_ne!(
+            fingerprint,
+            fingerprint_with_config(b"Correct horse battery staple", &FAST_CONFIG).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_matches() {
+        assert!(fingerprint_matches("acid-baker-zoom", "acid-baker-zoom"));
+        assert!(fingerprint_matches("acid-baker-zoom", " Acid Baker  ZOOM "));
+        assert!(!fingerprint_matches("acid-baker-zoom", "acid-baker"));
+        assert!(!fingerprint_matches("acid-baker-zoom", "acid-baker-zoo"));
+    }
+}
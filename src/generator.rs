@@ -15,8 +15,9 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use crate::wordlist::{get_wordlist, wordlist_size};
+use crate::wordlist::{Wordlist, get_wordlist};
 use anyhow::Result;
+use base64ct::{Base64UrlUnpadded, Encoding as _};
 use chacha20::ChaCha20;
 use chacha20::cipher::{KeyIvInit, StreamCipher};
 use zeroize::Zeroizing;
@@ -24,73 +25,1139 @@ use zeroize::Zeroizing;
 const ALPHABET: &[u8] =
     b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789!@#$%^&*()_+-=[]{}|;:,.<>?/~";
 
-pub fn generate_mnemonic(key: &[u8; 32], word_count: usize) -> Result<Zeroizing<String>> {
-    let wordlist = get_wordlist();
-    let wordlist_len = wordlist_size();
+pub const MAX_SEPARATOR_LEN: usize = 8;
 
-    let mut cipher = ChaCha20::new(key.into(), &[0u8; 12].into());
-    let mut words = Vec::with_capacity(word_count);
+const MNEMONIC_DIGITS: &[u8] = b"0123456789";
+const MNEMONIC_SYMBOLS: &[u8] = b"!#$%&*+=?@";
 
-    let max_multiple = 65536 / wordlist_len as u32;
-    let rejection_threshold = (max_multiple * wordlist_len as u32) as u16;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MnemonicFormat {
+    pub separator: String,
+    pub capitalize: bool,
+    pub digit: bool,
+    pub symbol: bool,
+}
 
-    let mut buffer = Zeroizing::new(vec![0u8; 512]);
-    cipher.apply_keystream(&mut buffer);
-    let mut pos = 0;
+impl Default for MnemonicFormat {
+    fn default() -> Self {
+        Self {
+            separator: "-".to_string(),
+            capitalize: false,
+            digit: false,
+            symbol: false,
+        }
+    }
+}
+
+impl MnemonicFormat {
+    pub fn check(&self) -> Result<()> {
+        if self.separator.len() > MAX_SEPARATOR_LEN {
+            anyhow::bail!(
+                "Separator must be at most {} bytes, got {}",
+                MAX_SEPARATOR_LEN,
+                self.separator.len()
+            );
+        }
+        if self.separator.chars().any(|c| c.is_control()) {
+            anyhow::bail!("Separator cannot contain control characters");
+        }
+        Ok(())
+    }
 
-    while words.len() < word_count {
-        if pos + 1 >= buffer.len() {
-            cipher.apply_keystream(&mut buffer);
-            pos = 0;
+    pub fn check_word_count(&self, word_count: usize) -> Result<()> {
+        if !(self.digit || self.symbol) {
+            return Ok(());
+        }
+        if word_count == 0 {
+            anyhow::bail!("Cannot add a digit or symbol to an empty mnemonic");
+        }
+        // Positions are drawn with 16-bit samples
+        if word_count > 65536 {
+            anyhow::bail!(
+                "Cannot add a digit or symbol to more than 65536 words, got {}",
+                word_count
+            );
         }
+        Ok(())
+    }
 
-        let random_u16 = u16::from_le_bytes([buffer[pos], buffer[pos + 1]]);
-        pos += 2;
+    // Only the injected characters themselves are counted; the word they are
+    // attached to adds a little more, which is left out as a safety margin.
+    pub fn added_entropy(&self) -> f64 {
+        let mut bits = 0.0;
+        if self.digit {
+            bits += (MNEMONIC_DIGITS.len() as f64).log2();
+        }
+        if self.symbol {
+            bits += (MNEMONIC_SYMBOLS.len() as f64).log2();
+        }
+        bits
+    }
+}
 
+// Like sample_index, but on 16-bit samples so lists longer than 256 words
+// stay unbiased.
+fn sample_index_u16(keystream: &mut Keystream, n: usize) -> usize {
+    let rejection_threshold = 65536 - (65536 % n as u32);
+    loop {
+        let random_u16 = keystream.next_u16() as u32;
         if random_u16 < rejection_threshold {
-            let index = (random_u16 % wordlist_len) as usize;
-            words.push(wordlist[index]);
+            return (random_u16 % n as u32) as usize;
         }
     }
+}
+
+pub fn generate_mnemonic(key: &[u8; 32], word_count: usize) -> Result<Zeroizing<String>> {
+    mnemonic_from_words(key, get_wordlist(), word_count, &MnemonicFormat::default())
+}
+
+pub fn generate_mnemonic_with_format(
+    key: &[u8; 32],
+    wordlist: &Wordlist,
+    word_count: usize,
+    format: &MnemonicFormat,
+) -> Result<Zeroizing<String>> {
+    mnemonic_from_words(key, wordlist.words(), word_count, format)
+}
+
+fn mnemonic_from_words<S: AsRef<str>>(
+    key: &[u8; 32],
+    wordlist: &[S],
+    word_count: usize,
+    format: &MnemonicFormat,
+) -> Result<Zeroizing<String>> {
+    format.check()?;
+    format.check_word_count(word_count)?;
+
+    let mut keystream = Keystream::new(key, 512);
+
+    let words: Vec<&str> = (0..word_count)
+        .map(|_| wordlist[sample_index_u16(&mut keystream, wordlist.len())].as_ref())
+        .collect();
+
+    // Extras are drawn after all words, so the words match the plain
+    // mnemonic for the same key.
+    let digit = format.digit.then(|| {
+        let position = sample_index_u16(&mut keystream, word_count);
+        (
+            position,
+            MNEMONIC_DIGITS[sample_index_u16(&mut keystream, MNEMONIC_DIGITS.len())],
+        )
+    });
+    let symbol = format.symbol.then(|| {
+        let position = sample_index_u16(&mut keystream, word_count);
+        (
+            position,
+            MNEMONIC_SYMBOLS[sample_index_u16(&mut keystream, MNEMONIC_SYMBOLS.len())],
+        )
+    });
+
+    let mut mnemonic = Zeroizing::new(String::new());
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            mnemonic.push_str(&format.separator);
+        }
+
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            if format.capitalize {
+                mnemonic.extend(first.to_uppercase());
+            } else {
+                mnemonic.push(first);
+            }
+            mnemonic.push_str(chars.as_str());
+        }
+
+        for (position, ch) in [digit, symbol].into_iter().flatten() {
+            if position == i {
+                mnemonic.push(ch as char);
+            }
+        }
+    }
+
+    Ok(mnemonic)
+}
+
+pub fn mnemonic_entropy(word_count: usize, wordlist_len: usize) -> f64 {
+    word_count as f64 * (wordlist_len as f64).log2()
+}
+
+#[derive(Debug)]
+pub struct WordCheck {
+    pub word: Zeroizing<String>,
+    pub valid: bool,
+    pub suggestions: Vec<String>,
+}
+
+fn strip_extras(token: &str) -> &str {
+    token.trim_end_matches(|c: char| {
+        c.is_ascii()
+            && (MNEMONIC_DIGITS.contains(&(c as u8)) || MNEMONIC_SYMBOLS.contains(&(c as u8)))
+    })
+}
+
+// Capitalization and an attached digit or symbol are accepted whether or not
+// the format asks for them; the derivation check catches any difference.
+fn lookup_form(token: &str) -> Zeroizing<String> {
+    Zeroizing::new(strip_extras(token).to_lowercase())
+}
+
+fn is_mnemonic_word(token: &str, wordlist: &Wordlist) -> bool {
+    wordlist.contains(token)
+        || wordlist.contains(&token.to_lowercase())
+        || wordlist.contains(strip_extras(token))
+        || wordlist.contains(&lookup_form(token))
+}
+
+// Splits on the separator (or whitespace when there is none), rejoining
+// pieces that together form a listed word such as "t-shirt".
+fn split_mnemonic(phrase: &str, wordlist: &Wordlist, separator: &str) -> Vec<Zeroizing<String>> {
+    let pieces: Vec<&str> = if separator.trim().is_empty() {
+        phrase.split_whitespace().collect()
+    } else {
+        phrase
+            .split(separator)
+            .map(str::trim)
+            .filter(|piece| !piece.is_empty())
+            .collect()
+    };
+
+    let max_join = if separator.is_empty() {
+        1
+    } else {
+        wordlist
+            .words()
+            .iter()
+            .map(|word| word.matches(separator).count() + 1)
+            .max()
+            .unwrap_or(1)
+    };
+
+    let mut tokens = Vec::with_capacity(pieces.len());
+    let mut i = 0;
+    while i < pieces.len() {
+        let joined = (2..=max_join.min(pieces.len() - i))
+            .rev()
+            .map(|n| (n, Zeroizing::new(pieces[i..i + n].join(separator))))
+            .find(|(_, token)| is_mnemonic_word(token, wordlist));
+
+        match joined {
+            Some((n, token)) => {
+                tokens.push(token);
+                i += n;
+            }
+            None => {
+                tokens.push(Zeroizing::new(pieces[i].to_string()));
+                i += 1;
+            }
+        }
+    }
+
+    tokens
+}
+
+pub fn check_mnemonic(
+    phrase: &str,
+    wordlist: &Wordlist,
+    format: &MnemonicFormat,
+) -> Result<Vec<WordCheck>> {
+    format.check()?;
+
+    let checks: Vec<WordCheck> = split_mnemonic(phrase, wordlist, &format.separator)
+        .into_iter()
+        .map(|word| {
+            let valid = is_mnemonic_word(&word, wordlist);
+            let suggestions = if valid {
+                Vec::new()
+            } else {
+                wordlist
+                    .suggest(&lookup_form(&word))
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect()
+            };
+            WordCheck {
+                word,
+                valid,
+                suggestions,
+            }
+        })
+        .collect();
+
+    if checks.is_empty() {
+        anyhow::bail!("Mnemonic cannot be empty");
+    }
+
+    Ok(checks)
+}
+
+// 1-based positions where the phrase differs from the one derived from the
+// key; an empty result means the phrase matches.
+pub fn mnemonic_mismatches(
+    key: &[u8; 32],
+    phrase: &str,
+    wordlist: &Wordlist,
+    word_count: usize,
+    format: &MnemonicFormat,
+) -> Result<Vec<usize>> {
+    let expected = generate_mnemonic_with_format(key, wordlist, word_count, format)?;
+    let expected = split_mnemonic(&expected, wordlist, &format.separator);
+    let actual = split_mnemonic(phrase, wordlist, &format.separator);
+
+    Ok((0..expected.len().max(actual.len()))
+        .filter(|&i| expected.get(i).map(|w| w.as_str()) != actual.get(i).map(|w| w.as_str()))
+        .map(|i| i + 1)
+        .collect())
+}
+
+const MAX_POLICY_CANDIDATES: usize = 1 << 16;
+const MAX_ENTROPY_DP_STEPS: usize = 1 << 27;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharClass {
+    Uppercase,
+    Lowercase,
+    Digit,
+    Symbol,
+}
+
+impl CharClass {
+    pub const ALL: [CharClass; 4] = [
+        CharClass::Uppercase,
+        CharClass::Lowercase,
+        CharClass::Digit,
+        CharClass::Symbol,
+    ];
+
+    pub fn of(byte: u8) -> Self {
+        if byte.is_ascii_uppercase() {
+            CharClass::Uppercase
+        } else if byte.is_ascii_lowercase() {
+            CharClass::Lowercase
+        } else if byte.is_ascii_digit() {
+            CharClass::Digit
+        } else {
+            CharClass::Symbol
+        }
+    }
+}
 
-    Ok(Zeroizing::new(words.join("-")))
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub uppercase: bool,
+    pub lowercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    pub alphabet: Option<String>,
+    pub exclude: String,
+    pub min_per_class: usize,
+    pub no_repeats: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            uppercase: true,
+            lowercase: true,
+            digits: true,
+            symbols: true,
+            alphabet: None,
+            exclude: String::new(),
+            min_per_class: 0,
+            no_repeats: false,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    fn allows(&self, class: CharClass) -> bool {
+        match class {
+            CharClass::Uppercase => self.uppercase,
+            CharClass::Lowercase => self.lowercase,
+            CharClass::Digit => self.digits,
+            CharClass::Symbol => self.symbols,
+        }
+    }
+
+    pub fn effective_alphabet(&self) -> Result<Vec<u8>> {
+        let base = match &self.alphabet {
+            Some(custom) => {
+                if let Some(c) = custom.chars().find(|c| !c.is_ascii_graphic()) {
+                    anyhow::bail!(
+                        "Custom alphabet may only contain printable ASCII characters, found {:?}",
+                        c
+                    );
+                }
+
+                let bytes = custom.as_bytes();
+                for (i, byte) in bytes.iter().enumerate() {
+                    if bytes[..i].contains(byte) {
+                        anyhow::bail!(
+                            "Custom alphabet contains duplicate character \"{}\"",
+                            *byte as char
+                        );
+                    }
+                }
+                bytes
+            }
+            None => ALPHABET,
+        };
+
+        let alphabet: Vec<u8> = base
+            .iter()
+            .copied()
+            .filter(|&b| self.allows(CharClass::of(b)))
+            .filter(|b| !self.exclude.as_bytes().contains(b))
+            .collect();
+
+        if alphabet.len() < 2 {
+            anyhow::bail!(
+                "Password policy leaves {} usable character(s), at least 2 are required",
+                alphabet.len()
+            );
+        }
+
+        Ok(alphabet)
+    }
+
+    pub fn required_classes(&self, alphabet: &[u8]) -> Vec<CharClass> {
+        if self.min_per_class == 0 {
+            return Vec::new();
+        }
+
+        CharClass::ALL
+            .into_iter()
+            .filter(|&class| alphabet.iter().any(|&b| CharClass::of(b) == class))
+            .collect()
+    }
+
+    pub fn check_length(&self, alphabet: &[u8], password_length: usize) -> Result<()> {
+        let required = self.required_classes(alphabet);
+
+        if required.len().saturating_mul(self.min_per_class) > password_length {
+            anyhow::bail!(
+                "Password length {} cannot hold {} character(s) from each of {} classes",
+                password_length,
+                self.min_per_class,
+                required.len()
+            );
+        }
+        Ok(())
+    }
+
+    pub fn entropy(&self, password_length: usize) -> Result<f64> {
+        let alphabet = self.effective_alphabet()?;
+        self.check_length(&alphabet, password_length)?;
+        let alphabet_size = alphabet.len() as f64;
+
+        if password_length == 0 {
+            return Ok(0.0);
+        }
+
+        let unconstrained = if self.no_repeats {
+            alphabet_size.log2() + (password_length - 1) as f64 * (alphabet_size - 1.0).log2()
+        } else {
+            password_length as f64 * alphabet_size.log2()
+        };
+
+        let acceptance = self.acceptance_probability(&alphabet, password_length)?;
+        if acceptance <= 0.0 {
+            anyhow::bail!(
+                "Password policy cannot be satisfied at length {}",
+                password_length
+            );
+        }
+
+        Ok(unconstrained + acceptance.log2())
+    }
+
+    // Whole-candidate rejection keeps the output uniform over compliant
+    // passwords, so the exact entropy is the unconstrained entropy plus the
+    // log2 of the probability that a single candidate is accepted.
+    fn acceptance_probability(&self, alphabet: &[u8], password_length: usize) -> Result<f64> {
+        let required = self.required_classes(alphabet);
+        if required.is_empty() {
+            return Ok(1.0);
+        }
+
+        let class_sizes: Vec<usize> = required
+            .iter()
+            .map(|&class| {
+                alphabet
+                    .iter()
+                    .filter(|&&b| CharClass::of(b) == class)
+                    .count()
+            })
+            .collect();
+
+        if self.no_repeats {
+            self.acceptance_probability_no_repeats(&class_sizes, alphabet.len(), password_length)
+        } else {
+            Ok(self.acceptance_probability_independent(
+                &class_sizes,
+                alphabet.len(),
+                password_length,
+            ))
+        }
+    }
+
+    fn acceptance_probability_independent(
+        &self,
+        class_sizes: &[usize],
+        alphabet_size: usize,
+        password_length: usize,
+    ) -> f64 {
+        let ln_factorial: Vec<f64> = std::iter::once(0.0)
+            .chain((1..=password_length).scan(0.0, |acc, i| {
+                *acc += (i as f64).ln();
+                Some(*acc)
+            }))
+            .collect();
+
+        // dp[j]: probability mass of the first j positions being filled by
+        // the classes processed so far, each meeting its minimum.
+        let mut dp = vec![0.0; password_length + 1];
+        dp[0] = 1.0;
+
+        for &size in class_sizes {
+            let ln_p = (size as f64 / alphabet_size as f64).ln();
+            let mut next = vec![0.0; password_length + 1];
+
+            for (j, slot) in next.iter_mut().enumerate() {
+                for k in self.min_per_class..=j {
+                    if dp[j - k] == 0.0 {
+                        continue;
+                    }
+                    let ln_binomial = ln_factorial[j] - ln_factorial[k] - ln_factorial[j - k];
+                    *slot += dp[j - k] * (ln_binomial + k as f64 * ln_p).exp();
+                }
+            }
+
+            dp = next;
+        }
+
+        dp[password_length]
+    }
+
+    fn acceptance_probability_no_repeats(
+        &self,
+        class_sizes: &[usize],
+        alphabet_size: usize,
+        password_length: usize,
+    ) -> Result<f64> {
+        let classes = class_sizes.len();
+        let too_complex =
+            || anyhow::anyhow!("Password policy is too complex to compute its exact entropy");
+
+        let radix = self.min_per_class.checked_add(1).ok_or_else(too_complex)?;
+        let count_states = radix.checked_pow(classes as u32).ok_or_else(too_complex)?;
+        let steps = count_states
+            .checked_mul(classes * classes)
+            .and_then(|steps| steps.checked_mul(password_length))
+            .ok_or_else(too_complex)?;
+
+        if steps > MAX_ENTROPY_DP_STEPS {
+            return Err(too_complex());
+        }
+
+        let state = |counts: usize, last: usize| counts * classes + last;
+        let stride = |class: usize| radix.pow(class as u32);
+        let bump = |counts: usize, class: usize| {
+            if (counts / stride(class)) % radix < self.min_per_class {
+                counts + stride(class)
+            } else {
+                counts
+            }
+        };
+
+        // The class of each character is a Markov chain: the next character
+        // is uniform over the alphabet minus the previous character.
+        let mut dp = vec![0.0; count_states * classes];
+        for (class, &size) in class_sizes.iter().enumerate() {
+            dp[state(bump(0, class), class)] += size as f64 / alphabet_size as f64;
+        }
+
+        for _ in 1..password_length {
+            let mut next = vec![0.0; count_states * classes];
+
+            for counts in 0..count_states {
+                for last in 0..classes {
+                    let mass = dp[state(counts, last)];
+                    if mass == 0.0 {
+                        continue;
+                    }
+
+                    for (class, &size) in class_sizes.iter().enumerate() {
+                        let choices = if class == last { size - 1 } else { size };
+                        if choices == 0 {
+                            continue;
+                        }
+                        next[state(bump(counts, class), class)] +=
+                            mass * choices as f64 / (alphabet_size - 1) as f64;
+                    }
+                }
+            }
+
+            dp = next;
+        }
+
+        let complete = count_states - 1;
+        Ok((0..classes).map(|last| dp[state(complete, last)]).sum())
+    }
+
+    fn satisfied_by(&self, required: &[CharClass], candidate: &[u8]) -> bool {
+        required.iter().all(|&class| {
+            candidate
+                .iter()
+                .filter(|&&b| CharClass::of(b) == class)
+                .count()
+                >= self.min_per_class
+        })
+    }
+}
+
+struct Keystream {
+    cipher: ChaCha20,
+    buffer: Zeroizing<Vec<u8>>,
+    pos: usize,
+}
+
+impl Keystream {
+    fn new(key: &[u8; 32], buffer_len: usize) -> Self {
+        let mut cipher = ChaCha20::new(key.into(), &[0u8; 12].into());
+        let mut buffer = Zeroizing::new(vec![0u8; buffer_len]);
+        cipher.apply_keystream(&mut buffer);
+
+        Self {
+            cipher,
+            buffer,
+            pos: 0,
+        }
+    }
+
+    // Refills XOR the next keystream block into the exhausted buffer rather
+    // than zeroing it first; outputs beyond the first block depend on this.
+    fn next_byte(&mut self) -> u8 {
+        if self.pos >= self.buffer.len() {
+            self.cipher.apply_keystream(&mut self.buffer);
+            self.pos = 0;
+        }
+
+        let byte = self.buffer[self.pos];
+        self.pos += 1;
+        byte
+    }
+
+    fn next_u16(&mut self) -> u16 {
+        u16::from_le_bytes([self.next_byte(), self.next_byte()])
+    }
 }
 
 pub fn generate_password(key: &[u8; 32], password_length: usize) -> Result<Zeroizing<String>> {
-    let mut cipher = ChaCha20::new(key.into(), &[0u8; 12].into());
+    generate_password_with_policy(key, password_length, &PasswordPolicy::default())
+}
+
+pub fn generate_password_with_policy(
+    key: &[u8; 32],
+    password_length: usize,
+    policy: &PasswordPolicy,
+) -> Result<Zeroizing<String>> {
+    let alphabet = policy.effective_alphabet()?;
+    policy.check_length(&alphabet, password_length)?;
+    let required = policy.required_classes(&alphabet);
 
-    let mut password_bytes = Zeroizing::new(Vec::with_capacity(password_length));
+    let mut keystream = Keystream::new(key, 1024);
 
-    let alphabet_size = ALPHABET.len();
+    let alphabet_size = alphabet.len();
     let rejection_threshold = 256 - (256 % alphabet_size);
 
-    let mut buffer = Zeroizing::new(vec![0u8; 1024]);
-    cipher.apply_keystream(&mut buffer);
-    let mut pos = 0;
+    for _ in 0..MAX_POLICY_CANDIDATES {
+        let mut password_bytes = Zeroizing::new(Vec::with_capacity(password_length));
+
+        while password_bytes.len() < password_length {
+            let random_byte = keystream.next_byte();
+
+            if (random_byte as usize) < rejection_threshold {
+                let index = (random_byte as usize) % alphabet_size;
+                let ch = alphabet[index];
+
+                if policy.no_repeats && password_bytes.last() == Some(&ch) {
+                    continue;
+                }
+
+                password_bytes.push(ch);
+            }
+        }
+
+        if policy.satisfied_by(&required, &password_bytes) {
+            let result = String::from_utf8(password_bytes.to_vec())?;
+            return Ok(Zeroizing::new(result));
+        }
+    }
+
+    anyhow::bail!("Password policy is too restrictive to satisfy")
+}
+
+pub const MIN_PIN_LENGTH: usize = 4;
+pub const MAX_PIN_LENGTH: usize = 16;
+
+const PIN_DIGITS: &[u8] = b"0123456789";
+const DAYS_IN_MONTH: [u32; 12] = [31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+const MIN_BIRTH_YEAR: u32 = 1900;
+const MAX_BIRTH_YEAR: u32 = 2099;
+
+pub fn check_pin_length(length: usize) -> Result<()> {
+    if !(MIN_PIN_LENGTH..=MAX_PIN_LENGTH).contains(&length) {
+        anyhow::bail!(
+            "PIN length must be between {} and {} digits, got {}",
+            MIN_PIN_LENGTH,
+            MAX_PIN_LENGTH,
+            length
+        );
+    }
+    Ok(())
+}
+
+// Repeats of a shorter block, such as 0000, 1212 or 123123.
+fn is_repeating_pin(pin: &[u8]) -> bool {
+    (1..pin.len())
+        .filter(|period| pin.len().is_multiple_of(*period))
+        .any(|period| (period..pin.len()).all(|i| pin[i] == pin[i - period]))
+}
+
+// Runs of consecutive digits in either direction, such as 1234 or 9876.
+fn is_sequential_pin(pin: &[u8]) -> bool {
+    [1i16, -1].iter().any(|&step| {
+        pin.windows(2)
+            .all(|pair| pair[1] as i16 - pair[0] as i16 == step)
+    })
+}
+
+fn parse_digits(digits: &[u8]) -> u32 {
+    digits
+        .iter()
+        .fold(0, |value, &digit| value * 10 + (digit - b'0') as u32)
+}
+
+fn is_day_month(day: u32, month: u32) -> bool {
+    (1..=12).contains(&month) && (1..=DAYS_IN_MONTH[month as usize - 1]).contains(&day)
+}
+
+fn is_full_year(year: u32) -> bool {
+    (MIN_BIRTH_YEAR..=MAX_BIRTH_YEAR).contains(&year)
+}
 
-    while password_bytes.len() < password_length {
-        if pos >= buffer.len() {
-            cipher.apply_keystream(&mut buffer);
-            pos = 0;
+// DDMM, MMDD and YYYY for four digits; DDMMYY, MMDDYY and YYMMDD for six;
+// DDMMYYYY, MMDDYYYY and YYYYMMDD for eight.
+fn is_date_pin(pin: &[u8]) -> bool {
+    let field = |start: usize, len: usize| parse_digits(&pin[start..start + len]);
+
+    match pin.len() {
+        4 => {
+            is_day_month(field(0, 2), field(2, 2))
+                || is_day_month(field(2, 2), field(0, 2))
+                || is_full_year(field(0, 4))
+        }
+        6 => {
+            is_day_month(field(0, 2), field(2, 2))
+                || is_day_month(field(2, 2), field(0, 2))
+                || is_day_month(field(4, 2), field(2, 2))
+        }
+        8 => {
+            (is_day_month(field(0, 2), field(2, 2)) && is_full_year(field(4, 4)))
+                || (is_day_month(field(2, 2), field(0, 2)) && is_full_year(field(4, 4)))
+                || (is_full_year(field(0, 4)) && is_day_month(field(6, 2), field(4, 2)))
         }
+        _ => false,
+    }
+}
 
-        let random_byte = buffer[pos];
-        pos += 1;
+pub fn is_weak_pin(pin: &[u8]) -> bool {
+    is_repeating_pin(pin) || is_sequential_pin(pin) || is_date_pin(pin)
+}
 
-        if (random_byte as usize) < rejection_threshold {
-            let index = (random_byte as usize) % alphabet_size;
-            password_bytes.push(ALPHABET[index]);
+fn mobius(mut n: usize) -> i64 {
+    let mut result = 1;
+    let mut factor = 2;
+    while factor * factor <= n {
+        if n.is_multiple_of(factor) {
+            n /= factor;
+            if n.is_multiple_of(factor) {
+                return 0;
+            }
+            result = -result;
+        }
+        factor += 1;
+    }
+    if n > 1 {
+        result = -result;
+    }
+    result
+}
+
+fn date_pins(length: usize) -> Vec<String> {
+    let mut pins = Vec::new();
+    let years = |digits: usize| -> Vec<u32> {
+        if digits == 4 {
+            (MIN_BIRTH_YEAR..=MAX_BIRTH_YEAR).collect()
+        } else {
+            (0..100).collect()
+        }
+    };
+
+    for month in 1..=12u32 {
+        for day in 1..=DAYS_IN_MONTH[month as usize - 1] {
+            match length {
+                4 => {
+                    pins.push(format!("{:02}{:02}", day, month));
+                    pins.push(format!("{:02}{:02}", month, day));
+                }
+                6 | 8 => {
+                    let year_digits = length - 4;
+                    for year in years(year_digits) {
+                        let year = format!("{:0width$}", year, width = year_digits);
+                        pins.push(format!("{:02}{:02}{}", day, month, year));
+                        pins.push(format!("{:02}{:02}{}", month, day, year));
+                        pins.push(format!("{}{:02}{:02}", year, month, day));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    if length == 4 {
+        pins.extend(years(4).into_iter().map(|year| year.to_string()));
+    }
+
+    pins.sort();
+    pins.dedup();
+    pins
+}
+
+// Exact size of the blocklist: repeating PINs are counted with Möbius
+// inversion over the primitive strings, the few sequences and dates are
+// enumerated and deduplicated against the repeats.
+pub fn weak_pin_count(length: usize) -> u64 {
+    let primitive: i64 = (1..=length)
+        .filter(|d| length.is_multiple_of(*d))
+        .map(|d| mobius(length / d) * 10i64.pow(d as u32))
+        .sum();
+    let repeating = 10u64.pow(length as u32) - primitive as u64;
+
+    let sequential = if length <= 10 {
+        2 * (11 - length) as u64
+    } else {
+        0
+    };
+
+    let dates = date_pins(length)
+        .iter()
+        .filter(|pin| !is_repeating_pin(pin.as_bytes()) && !is_sequential_pin(pin.as_bytes()))
+        .count() as u64;
+
+    repeating + sequential + dates
+}
+
+pub fn pin_entropy(length: usize, reject_weak: bool) -> f64 {
+    let total = 10f64.powi(length as i32);
+    if reject_weak {
+        (total - weak_pin_count(length) as f64).log2()
+    } else {
+        total.log2()
+    }
+}
+
+pub fn generate_pin(key: &[u8; 32], length: usize, reject_weak: bool) -> Result<Zeroizing<String>> {
+    check_pin_length(length)?;
+
+    let mut keystream = Keystream::new(key, 1024);
+    let rejection_threshold = 256 - (256 % PIN_DIGITS.len());
+
+    for _ in 0..MAX_POLICY_CANDIDATES {
+        let mut pin = Zeroizing::new(Vec::with_capacity(length));
+
+        while pin.len() < length {
+            let random_byte = keystream.next_byte();
+
+            if (random_byte as usize) < rejection_threshold {
+                pin.push(PIN_DIGITS[(random_byte as usize) % PIN_DIGITS.len()]);
+            }
+        }
+
+        if !reject_weak || !is_weak_pin(&pin) {
+            let result = String::from_utf8(pin.to_vec())?;
+            return Ok(Zeroizing::new(result));
+        }
+    }
+
+    anyhow::bail!("No PIN outside the weak PIN blocklist was found")
+}
+
+pub const MIN_SYLLABLES: usize = 1;
+pub const MAX_SYLLABLES: usize = 128;
+pub const MAX_APPENDED_DIGITS: usize = 16;
+
+const CONSONANTS: &[u8] = b"bdfghjklmnprstvz";
+const VOWELS: &[u8] = b"aeiou";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PronounceableOptions {
+    pub syllables: usize,
+    pub capitalize: bool,
+    pub digits: usize,
+}
+
+impl PronounceableOptions {
+    pub fn check(&self) -> Result<()> {
+        if !(MIN_SYLLABLES..=MAX_SYLLABLES).contains(&self.syllables) {
+            anyhow::bail!(
+                "Syllable count must be between {} and {}, got {}",
+                MIN_SYLLABLES,
+                MAX_SYLLABLES,
+                self.syllables
+            );
+        }
+        if self.digits > MAX_APPENDED_DIGITS {
+            anyhow::bail!(
+                "At most {} digits can be appended, got {}",
+                MAX_APPENDED_DIGITS,
+                self.digits
+            );
         }
+        Ok(())
     }
 
-    let result = String::from_utf8(password_bytes.to_vec())?;
+    pub fn syllable_entropy(&self) -> f64 {
+        ((CONSONANTS.len() * VOWELS.len()) as f64).log2()
+    }
+
+    pub fn capital_entropy(&self) -> f64 {
+        if self.capitalize {
+            (self.syllables as f64).log2()
+        } else {
+            0.0
+        }
+    }
+
+    pub fn digit_entropy(&self) -> f64 {
+        10f64.log2()
+    }
+
+    // Every combination of syllables, capital position and digits yields a
+    // distinct string, so the parts add up exactly.
+    pub fn entropy(&self) -> f64 {
+        self.syllables as f64 * self.syllable_entropy()
+            + self.capital_entropy()
+            + self.digits as f64 * self.digit_entropy()
+    }
+}
+
+fn sample_index(keystream: &mut Keystream, n: usize) -> usize {
+    let rejection_threshold = 256 - (256 % n);
+    loop {
+        let random_byte = keystream.next_byte() as usize;
+        if random_byte < rejection_threshold {
+            return random_byte % n;
+        }
+    }
+}
+
+pub fn generate_pronounceable(
+    key: &[u8; 32],
+    options: &PronounceableOptions,
+) -> Result<Zeroizing<String>> {
+    options.check()?;
+
+    let mut keystream = Keystream::new(key, 1024);
+    let mut password = Zeroizing::new(Vec::with_capacity(options.syllables * 2 + options.digits));
+
+    for _ in 0..options.syllables {
+        password.push(CONSONANTS[sample_index(&mut keystream, CONSONANTS.len())]);
+        password.push(VOWELS[sample_index(&mut keystream, VOWELS.len())]);
+    }
+
+    if options.capitalize {
+        let syllable = sample_index(&mut keystream, options.syllables);
+        password[syllable * 2].make_ascii_uppercase();
+    }
+
+    for _ in 0..options.digits {
+        password.push(PIN_DIGITS[sample_index(&mut keystream, PIN_DIGITS.len())]);
+    }
+
+    let result = String::from_utf8(password.to_vec())?;
+    Ok(Zeroizing::new(result))
+}
+
+pub const MIN_RAW_BYTES: usize = 1;
+pub const MAX_RAW_BYTES: usize = 1 << 16;
+// Base58 encoding is quadratic in the input length
+pub const MAX_BASE58_BYTES: usize = 1024;
+
+const HEX_ALPHABET: &[u8; 16] = b"0123456789abcdef";
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const Z85_ALPHABET: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Hex,
+    Base32,
+    Base58,
+    Base64Url,
+    Z85,
+}
+
+impl Encoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Hex => "hex",
+            Encoding::Base32 => "base32",
+            Encoding::Base58 => "base58",
+            Encoding::Base64Url => "base64url",
+            Encoding::Z85 => "z85",
+        }
+    }
+
+    pub fn check_length(&self, bytes: usize) -> Result<()> {
+        if *self == Encoding::Z85 && !bytes.is_multiple_of(4) {
+            anyhow::bail!("Z85 needs a multiple of 4 bytes, got {}", bytes);
+        }
+        if *self == Encoding::Base58 && bytes > MAX_BASE58_BYTES {
+            anyhow::bail!(
+                "Base58 is limited to {} bytes, got {}",
+                MAX_BASE58_BYTES,
+                bytes
+            );
+        }
+        Ok(())
+    }
+
+    pub fn encode(&self, data: &[u8]) -> Result<Zeroizing<String>> {
+        match self {
+            Encoding::Hex => Ok(hex_encode(data)),
+            Encoding::Base32 => Ok(base32_encode(data)),
+            Encoding::Base58 => base58_encode(data),
+            Encoding::Base64Url => Ok(Zeroizing::new(Base64UrlUnpadded::encode_string(data))),
+            Encoding::Z85 => z85_encode(data),
+        }
+    }
+}
+
+pub fn hex_encode(data: &[u8]) -> Zeroizing<String> {
+    let mut encoded = Zeroizing::new(String::with_capacity(data.len() * 2));
+    for &byte in data {
+        encoded.push(HEX_ALPHABET[(byte >> 4) as usize] as char);
+        encoded.push(HEX_ALPHABET[(byte & 15) as usize] as char);
+    }
+    encoded
+}
+
+// RFC 4648 Base32 without padding; a trailing partial group is zero-filled.
+pub fn base32_encode(data: &[u8]) -> Zeroizing<String> {
+    let mut encoded = Zeroizing::new(String::with_capacity((data.len() * 8).div_ceil(5)));
+    let mut accumulator = 0u32;
+    let mut bits = 0u32;
+
+    for &byte in data {
+        accumulator = (accumulator << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[((accumulator >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[((accumulator << (5 - bits)) & 31) as usize] as char);
+    }
+
+    encoded
+}
+
+// Bitcoin alphabet; each leading zero byte becomes a leading '1'.
+pub fn base58_encode(data: &[u8]) -> Result<Zeroizing<String>> {
+    Encoding::Base58.check_length(data.len())?;
+
+    let leading_zeros = data.iter().take_while(|&&b| b == 0).count();
+    let mut digits = Zeroizing::new(Vec::with_capacity(data.len() * 138 / 100 + 1));
+
+    for &byte in &data[leading_zeros..] {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let mut encoded = Zeroizing::new(String::with_capacity(leading_zeros + digits.len()));
+    for _ in 0..leading_zeros {
+        encoded.push('1');
+    }
+    for &digit in digits.iter().rev() {
+        encoded.push(BASE58_ALPHABET[digit as usize] as char);
+    }
+
+    Ok(encoded)
+}
+
+// ZeroMQ RFC 32; the input length must be a multiple of four bytes.
+pub fn z85_encode(data: &[u8]) -> Result<Zeroizing<String>> {
+    Encoding::Z85.check_length(data.len())?;
+
+    let mut encoded = Zeroizing::new(String::with_capacity(data.len() / 4 * 5));
+    for chunk in data.chunks_exact(4) {
+        let value = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        for power in (0..5).rev() {
+            encoded.push(Z85_ALPHABET[(value / 85u32.pow(power) % 85) as usize] as char);
+        }
+    }
+
+    Ok(encoded)
+}
+
+pub fn check_raw_length(bytes: usize) -> Result<()> {
+    if !(MIN_RAW_BYTES..=MAX_RAW_BYTES).contains(&bytes) {
+        anyhow::bail!(
+            "Raw output must be between {} and {} bytes, got {}",
+            MIN_RAW_BYTES,
+            MAX_RAW_BYTES,
+            bytes
+        );
+    }
+    Ok(())
+}
+
+// Plain ChaCha20 keystream, without the Keystream refill behaviour, so the
+// output is exactly the first N keystream bytes for the derived key.
+pub fn expand_key(key: &[u8; 32], bytes: usize) -> Result<Zeroizing<Vec<u8>>> {
+    check_raw_length(bytes)?;
+
+    let mut cipher = ChaCha20::new(key.into(), &[0u8; 12].into());
+    let mut output = Zeroizing::new(vec![0u8; bytes]);
+    cipher.apply_keystream(&mut output);
+    Ok(output)
+}
 
-    Ok(Zeroizing::new(result))
+pub fn generate_raw(key: &[u8; 32], bytes: usize, encoding: Encoding) -> Result<Zeroizing<String>> {
+    let material = expand_key(key, bytes)?;
+    encoding.encode(&material)
+}
+
+// The expansion cannot add entropy beyond the 256-bit derived key.
+pub fn raw_entropy(bytes: usize) -> f64 {
+    (bytes * 8).min(256) as f64
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::wordlist::WordlistKind;
     use unicode_normalization::UnicodeNormalization;
 
     fn to_zeroizing_vec(v: Vec<String>) -> Vec<Zeroizing<String>> {
@@ -403,4 +1470,819 @@ mod tests {
             assert_eq!(*mnemonic_nfc, *mnemonic_nfd);
         }
     }
+
+    #[test]
+    fn test_default_policy_matches_password() {
+        let key = [42u8; 32];
+        let password = generate_password(&key, 48).unwrap();
+        let with_policy =
+            generate_password_with_policy(&key, 48, &PasswordPolicy::default()).unwrap();
+        assert_eq!(*password, *with_policy);
+
+        let alphabet = PasswordPolicy::default().effective_alphabet().unwrap();
+        assert_eq!(alphabet, ALPHABET);
+    }
+
+    #[test]
+    fn test_policy_classes_and_exclusions() {
+        let key = [42u8; 32];
+        let policy = PasswordPolicy {
+            symbols: false,
+            exclude: "0O1lI".to_string(),
+            ..PasswordPolicy::default()
+        };
+
+        let alphabet = policy.effective_alphabet().unwrap();
+        assert_eq!(alphabet.len(), 57);
+
+        let password = generate_password_with_policy(&key, 64, &policy).unwrap();
+        assert_eq!(password.len(), 64);
+        for ch in password.bytes() {
+            assert!(ch.is_ascii_alphanumeric());
+            assert!(!b"0O1lI".contains(&ch));
+        }
+    }
+
+    #[test]
+    fn test_policy_custom_alphabet() {
+        let key = [42u8; 32];
+        let policy = PasswordPolicy {
+            alphabet: Some("abcdef0123456789".to_string()),
+            ..PasswordPolicy::default()
+        };
+
+        let password1 = generate_password_with_policy(&key, 32, &policy).unwrap();
+        let password2 = generate_password_with_policy(&key, 32, &policy).unwrap();
+        assert_eq!(*password1, *password2);
+        assert!(password1.bytes().all(|b| b.is_ascii_hexdigit()));
+        assert_eq!(policy.entropy(32).unwrap(), 128.0);
+    }
+
+    #[test]
+    fn test_policy_invalid_alphabets() {
+        let duplicate = PasswordPolicy {
+            alphabet: Some("abca".to_string()),
+            ..PasswordPolicy::default()
+        };
+        assert!(duplicate.effective_alphabet().is_err());
+
+        let whitespace = PasswordPolicy {
+            alphabet: Some("ab c".to_string()),
+            ..PasswordPolicy::default()
+        };
+        assert!(whitespace.effective_alphabet().is_err());
+
+        let too_small = PasswordPolicy {
+            uppercase: false,
+            lowercase: false,
+            symbols: false,
+            exclude: "012345678".to_string(),
+            ..PasswordPolicy::default()
+        };
+        assert!(too_small.effective_alphabet().is_err());
+    }
+
+    #[test]
+    fn test_policy_no_repeats() {
+        let key = [42u8; 32];
+        let policy = PasswordPolicy {
+            alphabet: Some("ab".to_string()),
+            no_repeats: true,
+            ..PasswordPolicy::default()
+        };
+
+        let password = generate_password_with_policy(&key, 16, &policy).unwrap();
+        assert!(
+            password
+                .as_bytes()
+                .windows(2)
+                .all(|pair| pair[0] != pair[1])
+        );
+        assert_eq!(policy.entropy(16).unwrap(), 1.0);
+
+        let digits = PasswordPolicy {
+            uppercase: false,
+            lowercase: false,
+            symbols: false,
+            no_repeats: true,
+            ..PasswordPolicy::default()
+        };
+        let expected = 10f64.log2() + 5.0 * 9f64.log2();
+        assert!((digits.entropy(6).unwrap() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_policy_min_per_class() {
+        let policy = PasswordPolicy {
+            min_per_class: 2,
+            ..PasswordPolicy::default()
+        };
+
+        for seed in 0u8..32 {
+            let key = [seed; 32];
+            let password = generate_password_with_policy(&key, 12, &policy).unwrap();
+            for class in CharClass::ALL {
+                let count = password
+                    .bytes()
+                    .filter(|&b| CharClass::of(b) == class)
+                    .count();
+                assert!(count >= 2, "{:?} appears {} time(s)", class, count);
+            }
+        }
+
+        let key = [42u8; 32];
+        assert!(generate_password_with_policy(&key, 7, &policy).is_err());
+    }
+
+    fn count_compliant(policy: &PasswordPolicy, length: usize) -> usize {
+        let alphabet = policy.effective_alphabet().unwrap();
+        let required = policy.required_classes(&alphabet);
+        let total = alphabet.len().pow(length as u32);
+
+        (0..total)
+            .filter(|&n| {
+                let candidate: Vec<u8> = (0..length)
+                    .map(|i| alphabet[(n / alphabet.len().pow(i as u32)) % alphabet.len()])
+                    .collect();
+                let repeats_ok =
+                    !policy.no_repeats || candidate.windows(2).all(|pair| pair[0] != pair[1]);
+                repeats_ok && policy.satisfied_by(&required, &candidate)
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_class_coverage_exact_entropy() {
+        let cases = vec![
+            ("aB1", 4, 1, false),
+            ("aB1", 4, 1, true),
+            ("abC1!", 5, 1, false),
+            ("abC1!", 6, 1, true),
+            ("abCD12", 6, 2, false),
+            ("abCD12", 6, 2, true),
+        ];
+
+        for (alphabet, length, min_per_class, no_repeats) in cases {
+            let policy = PasswordPolicy {
+                alphabet: Some(alphabet.to_string()),
+                min_per_class,
+                no_repeats,
+                ..PasswordPolicy::default()
+            };
+
+            let expected = (count_compliant(&policy, length) as f64).log2();
+            let entropy = policy.entropy(length).unwrap();
+
+            assert!(
+                (entropy - expected).abs() < 1e-9,
+                "{} (length {}, min {}, no repeats {}): {} != {}",
+                alphabet,
+                length,
+                min_per_class,
+                no_repeats,
+                entropy,
+                expected
+            );
+        }
+
+        let policy = PasswordPolicy {
+            alphabet: Some("aB1".to_string()),
+            min_per_class: 1,
+            ..PasswordPolicy::default()
+        };
+        assert_eq!(count_compliant(&policy, 4), 36);
+    }
+
+    #[test]
+    fn test_class_coverage_entropy_default_alphabet() {
+        let policy = PasswordPolicy {
+            min_per_class: 1,
+            ..PasswordPolicy::default()
+        };
+
+        let entropy = policy.entropy(20).unwrap();
+        let unconstrained = 20.0 * 90f64.log2();
+        assert!(entropy < unconstrained);
+        assert!(unconstrained - entropy < 0.5);
+
+        let impossible = PasswordPolicy {
+            min_per_class: 3,
+            ..PasswordPolicy::default()
+        };
+        assert!(impossible.entropy(11).is_err());
+
+        let oversized = PasswordPolicy {
+            min_per_class: 100_000,
+            no_repeats: true,
+            ..PasswordPolicy::default()
+        };
+        assert!(oversized.entropy(32).is_err());
+        assert!(oversized.entropy(usize::MAX).is_err());
+        assert!(generate_password_with_policy(&[0u8; 32], 32, &oversized).is_err());
+    }
+
+    #[test]
+    fn test_class_coverage_deterministic() {
+        let policy = PasswordPolicy {
+            min_per_class: 1,
+            no_repeats: true,
+            ..PasswordPolicy::default()
+        };
+
+        for seed in 0u8..64 {
+            let key = [seed; 32];
+            let password1 = generate_password_with_policy(&key, 8, &policy).unwrap();
+            let password2 = generate_password_with_policy(&key, 8, &policy).unwrap();
+            assert_eq!(*password1, *password2);
+
+            for class in CharClass::ALL {
+                assert!(password1.bytes().any(|b| CharClass::of(b) == class));
+            }
+        }
+    }
+
+    #[test]
+    fn test_regression_class_coverage() {
+        let key = [42u8; 32];
+        let policy = PasswordPolicy {
+            min_per_class: 1,
+            ..PasswordPolicy::default()
+        };
+
+        let password = generate_password_with_policy(&key, 20, &policy).unwrap();
+        assert_eq!(*password, "!Zf(pCW&.29uDPgE6bev");
+    }
+
+    // Scheme v1 freezes the generators as well as the KDF, so each one is
+    // pinned on a fixed key; a change here needs a new derivation scheme.
+    #[test]
+    fn test_scheme_v1_generators_known_answers() {
+        use sha2::{Digest, Sha256};
+
+        let key = [42u8; 32];
+        let digest = |output: &str| -> String {
+            Sha256::digest(output.as_bytes())
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect()
+        };
+
+        assert_eq!(
+            *generate_mnemonic(&key, 8).unwrap(),
+            "supermom-excretion-follicle-saucy-slapstick-wasting-catching-antiques"
+        );
+        assert_eq!(
+            *generate_password(&key, 20).unwrap(),
+            "!Zf(pCW&.29uDPgE6bev"
+        );
+        assert_eq!(*generate_pin(&key, 6, false).unwrap(), "251091");
+        assert_eq!(*generate_pin(&key, 6, true).unwrap(), "228441");
+        assert_eq!(
+            *generate_raw(&key, 16, Encoding::Hex).unwrap(),
+            "98191f46e5830216445436978803697a"
+        );
+
+        let short = Wordlist::builtin(WordlistKind::EffShort2).unwrap();
+        assert_eq!(
+            *generate_mnemonic_with_format(&key, &short, 10, &MnemonicFormat::default()).unwrap(),
+            "antelope-tadpole-announcer-fixture-opium-timid-prairie-crystal-leprechaun-jealous"
+        );
+
+        // Long outputs run past the first keystream buffer and its refill
+        assert_eq!(
+            digest(&generate_mnemonic(&key, 512).unwrap()),
+            "ae000c0d6a198f7ae25335dbd60368ff09b1f9fe328f63ad919601ff1bc7a104"
+        );
+        assert_eq!(
+            digest(&generate_password(&key, 2048).unwrap()),
+            "7274ef36241da2c8d0d6bb98966aa6f2ef7aad710aa1aadd32697b74fd0f6f2d"
+        );
+    }
+
+    #[test]
+    fn test_weak_pin_patterns() {
+        let weak = [
+            "0000",
+            "1212",
+            "1234",
+            "9876",
+            "2512",
+            "1225",
+            "1987",
+            "123123",
+            "250690",
+            "19870412",
+            "04121987",
+            "0123456789",
+        ];
+        for pin in weak {
+            assert!(is_weak_pin(pin.as_bytes()), "{} should be weak", pin);
+        }
+
+        let strong = ["3827", "9134", "473951", "58203974"];
+        for pin in strong {
+            assert!(!is_weak_pin(pin.as_bytes()), "{} should not be weak", pin);
+        }
+    }
+
+    #[test]
+    fn test_weak_pin_count_matches_enumeration() {
+        for length in [4, 5, 6] {
+            let enumerated = (0..10u64.pow(length as u32))
+                .filter(|n| is_weak_pin(format!("{:0width$}", n, width = length).as_bytes()))
+                .count() as u64;
+            assert_eq!(weak_pin_count(length), enumerated, "length {}", length);
+        }
+    }
+
+    #[test]
+    fn test_pin_entropy() {
+        assert!((pin_entropy(4, false) - 10000f64.log2()).abs() < 1e-9);
+        assert!(pin_entropy(4, true) < pin_entropy(4, false));
+        assert!((pin_entropy(8, true) - (1e8 - weak_pin_count(8) as f64).log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pin_matches_digit_password() {
+        let key = [42u8; 32];
+        let policy = PasswordPolicy {
+            alphabet: Some("0123456789".to_string()),
+            ..PasswordPolicy::default()
+        };
+
+        for length in [4, 6, 16] {
+            let pin = generate_pin(&key, length, false).unwrap();
+            let password = generate_password_with_policy(&key, length, &policy).unwrap();
+            assert_eq!(*pin, *password);
+            assert!(pin.bytes().all(|b| b.is_ascii_digit()));
+        }
+    }
+
+    #[test]
+    fn test_pin_blocklist_is_deterministic() {
+        let weak_key = (0..=255u8)
+            .map(|i| [i; 32])
+            .find(|key| is_weak_pin(generate_pin(key, 4, false).unwrap().as_bytes()))
+            .expect("some key should yield a weak 4-digit PIN");
+
+        let filtered = generate_pin(&weak_key, 4, true).unwrap();
+        assert!(!is_weak_pin(filtered.as_bytes()));
+        assert_ne!(*filtered, *generate_pin(&weak_key, 4, false).unwrap());
+        assert_eq!(*filtered, *generate_pin(&weak_key, 4, true).unwrap());
+    }
+
+    #[test]
+    fn test_pin_length_limits() {
+        let key = [42u8; 32];
+        assert!(generate_pin(&key, MIN_PIN_LENGTH - 1, false).is_err());
+        assert!(generate_pin(&key, MAX_PIN_LENGTH + 1, false).is_err());
+        assert_eq!(generate_pin(&key, 8, true).unwrap().len(), 8);
+    }
+
+    #[test]
+    fn test_mnemonic_format_keeps_words() {
+        let key = [42u8; 32];
+        let plain = generate_mnemonic(&key, 8).unwrap();
+        let format = MnemonicFormat {
+            separator: " ".to_string(),
+            capitalize: true,
+            digit: true,
+            symbol: true,
+        };
+        let formatted =
+            generate_mnemonic_with_format(&key, &Wordlist::eff_large(), 8, &format).unwrap();
+
+        let words: Vec<String> = formatted
+            .split(' ')
+            .map(|word| {
+                word.chars()
+                    .filter(|c| c.is_ascii_alphabetic() || *c == '-')
+                    .collect::<String>()
+                    .to_lowercase()
+            })
+            .collect();
+        assert_eq!(words.join("-"), *plain);
+
+        for word in formatted.split(' ') {
+            assert!(word.chars().next().unwrap().is_uppercase());
+        }
+        assert_eq!(formatted.chars().filter(|c| c.is_ascii_digit()).count(), 1);
+        assert_eq!(
+            formatted
+                .bytes()
+                .filter(|b| MNEMONIC_SYMBOLS.contains(b))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_mnemonic_format_default_is_plain() {
+        let key = [7u8; 32];
+        assert_eq!(
+            *generate_mnemonic_with_format(
+                &key,
+                &Wordlist::eff_large(),
+                12,
+                &MnemonicFormat::default()
+            )
+            .unwrap(),
+            *generate_mnemonic(&key, 12).unwrap()
+        );
+
+        let joined = MnemonicFormat {
+            separator: String::new(),
+            ..MnemonicFormat::default()
+        };
+        assert_eq!(
+            *generate_mnemonic_with_format(&key, &Wordlist::eff_large(), 12, &joined).unwrap(),
+            generate_mnemonic(&key, 12).unwrap().replace('-', "")
+        );
+    }
+
+    #[test]
+    fn test_mnemonic_format_entropy() {
+        let format = MnemonicFormat {
+            digit: true,
+            symbol: true,
+            ..MnemonicFormat::default()
+        };
+        assert_eq!(MnemonicFormat::default().added_entropy(), 0.0);
+        assert!((format.added_entropy() - 2.0 * 10f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_mnemonic_format_invalid() {
+        let key = [42u8; 32];
+        let invalid = [
+            MnemonicFormat {
+                separator: "\n".to_string(),
+                ..MnemonicFormat::default()
+            },
+            MnemonicFormat {
+                separator: "-".repeat(MAX_SEPARATOR_LEN + 1),
+                ..MnemonicFormat::default()
+            },
+        ];
+        for format in invalid {
+            assert!(
+                generate_mnemonic_with_format(&key, &Wordlist::eff_large(), 8, &format).is_err()
+            );
+        }
+
+        let digit = MnemonicFormat {
+            digit: true,
+            ..MnemonicFormat::default()
+        };
+        assert!(generate_mnemonic_with_format(&key, &Wordlist::eff_large(), 0, &digit).is_err());
+
+        let symbol = MnemonicFormat {
+            symbol: true,
+            ..MnemonicFormat::default()
+        };
+        for format in [&digit, &symbol] {
+            assert!(
+                generate_mnemonic_with_format(&key, &Wordlist::eff_large(), 65537, format).is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_regression_mnemonic_format() {
+        let master = b"life";
+        let layers = to_zeroizing_vec(vec![
+            "out".to_string(),
+            "of".to_string(),
+            "balance".to_string(),
+        ]);
+        let config = crate::kdf::Argon2Config {
+            memory_kib: 8 * 1024,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let key = crate::kdf::derive_hierarchical(master, &layers, config).unwrap();
+        let format = MnemonicFormat {
+            separator: ".".to_string(),
+            capitalize: true,
+            digit: true,
+            symbol: true,
+        };
+
+        assert_eq!(
+            *generate_mnemonic_with_format(&key, &Wordlist::eff_large(), 6, &format).unwrap(),
+            "Upstream.Skid+.Cough.Subsiding.Revival4.Tribesman"
+        );
+    }
+
+    #[test]
+    fn test_mnemonic_custom_wordlist() {
+        let key = [42u8; 32];
+        let small = Wordlist::parse(WordlistKind::Custom, "alpha\nbravo\ncharlie\n").unwrap();
+        let mnemonic =
+            generate_mnemonic_with_format(&key, &small, 20, &MnemonicFormat::default()).unwrap();
+
+        assert_eq!(mnemonic.split('-').count(), 20);
+        assert!(
+            mnemonic
+                .split('-')
+                .all(|word| small.words().iter().any(|w| w == word))
+        );
+        assert!((mnemonic_entropy(20, small.len()) - 20.0 * 3f64.log2()).abs() < 1e-9);
+
+        // A power-of-two list needs no rejection at all
+        let text: String = (0..65536).map(|i| format!("w{}\n", i)).collect();
+        let full = Wordlist::parse(WordlistKind::Custom, &text).unwrap();
+        let mnemonic =
+            generate_mnemonic_with_format(&key, &full, 8, &MnemonicFormat::default()).unwrap();
+        assert_eq!(mnemonic.split('-').count(), 8);
+        assert_eq!(mnemonic_entropy(8, full.len()), 128.0);
+    }
+
+    #[test]
+    fn test_pronounceable_structure() {
+        let options = PronounceableOptions {
+            syllables: 16,
+            capitalize: false,
+            digits: 0,
+        };
+        let password = generate_pronounceable(&[42u8; 32], &options).unwrap();
+
+        assert_eq!(password.len(), 32);
+        for pair in password.as_bytes().chunks(2) {
+            assert!(CONSONANTS.contains(&pair[0]));
+            assert!(VOWELS.contains(&pair[1]));
+        }
+        assert_eq!(
+            *password,
+            *generate_pronounceable(&[42u8; 32], &options).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_pronounceable_capital_and_digits() {
+        let options = PronounceableOptions {
+            syllables: 12,
+            capitalize: true,
+            digits: 3,
+        };
+
+        for i in 0..32u8 {
+            let password = generate_pronounceable(&[i; 32], &options).unwrap();
+            let bytes = password.as_bytes();
+
+            assert_eq!(bytes.len(), 27);
+            assert!(bytes[24..].iter().all(|b| b.is_ascii_digit()));
+
+            let capitals: Vec<usize> = (0..24).filter(|&j| bytes[j].is_ascii_uppercase()).collect();
+            assert_eq!(capitals.len(), 1);
+            assert_eq!(capitals[0] % 2, 0);
+        }
+    }
+
+    #[test]
+    fn test_pronounceable_exact_entropy() {
+        let plain = PronounceableOptions {
+            syllables: 16,
+            capitalize: false,
+            digits: 0,
+        };
+        assert!((plain.entropy() - 16.0 * 80f64.log2()).abs() < 1e-9);
+
+        let shaped = PronounceableOptions {
+            syllables: 16,
+            capitalize: true,
+            digits: 2,
+        };
+        assert!((shaped.entropy() - (16.0 * 80f64.log2() + 4.0 + 2.0 * 10f64.log2())).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pronounceable_limits() {
+        let key = [42u8; 32];
+        let invalid = [
+            PronounceableOptions {
+                syllables: 0,
+                capitalize: false,
+                digits: 0,
+            },
+            PronounceableOptions {
+                syllables: MAX_SYLLABLES + 1,
+                capitalize: false,
+                digits: 0,
+            },
+            PronounceableOptions {
+                syllables: 8,
+                capitalize: false,
+                digits: MAX_APPENDED_DIGITS + 1,
+            },
+        ];
+
+        for options in invalid {
+            assert!(generate_pronounceable(&key, &options).is_err());
+        }
+    }
+
+    #[test]
+    fn test_regression_pronounceable() {
+        let master = b"life";
+        let layers = to_zeroizing_vec(vec![
+            "out".to_string(),
+            "of".to_string(),
+            "balance".to_string(),
+        ]);
+        let config = crate::kdf::Argon2Config {
+            memory_kib: 8 * 1024,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let key = crate::kdf::derive_hierarchical(master, &layers, config).unwrap();
+        let options = PronounceableOptions {
+            syllables: 8,
+            capitalize: true,
+            digits: 2,
+        };
+
+        assert_eq!(
+            *generate_pronounceable(&key, &options).unwrap(),
+            "vasumejabeNoseka82"
+        );
+    }
+
+    #[test]
+    fn test_hex_encode() {
+        assert_eq!(*hex_encode(&[]), "");
+        assert_eq!(*hex_encode(&[0x00, 0x0f, 0xa5, 0xff]), "000fa5ff");
+    }
+
+    #[test]
+    fn test_base32_rfc4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "MY"),
+            ("fo", "MZXQ"),
+            ("foo", "MZXW6"),
+            ("foob", "MZXW6YQ"),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI"),
+        ];
+
+        for (input, expected) in vectors {
+            assert_eq!(*base32_encode(input.as_bytes()), expected);
+        }
+    }
+
+    #[test]
+    fn test_base58_vectors() {
+        assert_eq!(*base58_encode(b"").unwrap(), "");
+        assert_eq!(
+            *base58_encode(b"Hello World!").unwrap(),
+            "2NEpo7TZRRrLZSi2U"
+        );
+        assert_eq!(
+            *base58_encode(&[0x00, 0x00, 0x28, 0x7f, 0xb4, 0xcd]).unwrap(),
+            "11233QC4"
+        );
+        assert_eq!(*base58_encode(&[0x00]).unwrap(), "1");
+        assert!(base58_encode(&[0xff; MAX_BASE58_BYTES]).is_ok());
+        assert!(base58_encode(&[0xff; MAX_BASE58_BYTES + 1]).is_err());
+    }
+
+    #[test]
+    fn test_base64url_unpadded() {
+        assert_eq!(*Encoding::Base64Url.encode(b"fooba").unwrap(), "Zm9vYmE");
+        assert_eq!(*Encoding::Base64Url.encode(&[0xfb, 0xff]).unwrap(), "-_8");
+    }
+
+    #[test]
+    fn test_z85_vectors() {
+        let data = [0x86, 0x4f, 0xd2, 0x6f, 0xb5, 0x59, 0xf7, 0x5b];
+        assert_eq!(*z85_encode(&data).unwrap(), "HelloWorld");
+        assert_eq!(*z85_encode(&[0xff; 4]).unwrap(), "%nSc0");
+        assert!(z85_encode(&data[..5]).is_err());
+    }
+
+    #[test]
+    fn test_expand_key_chacha20_keystream() {
+        // RFC 7539 appendix A.1, test vector 1: all-zero key and nonce
+        let expanded = expand_key(&[0u8; 32], 64).unwrap();
+        assert_eq!(
+            *hex_encode(&expanded),
+            "76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7\
+             da41597c5157488d7724e03fb8d84a376a43b8f41518a11cc387b669b2ee6586"
+        );
+
+        let short = expand_key(&[0u8; 32], 16).unwrap();
+        assert_eq!(&short[..], &expanded[..16]);
+    }
+
+    #[test]
+    fn test_raw_lengths() {
+        let key = [42u8; 32];
+        assert_eq!(generate_raw(&key, 32, Encoding::Hex).unwrap().len(), 64);
+        assert_eq!(generate_raw(&key, 20, Encoding::Base32).unwrap().len(), 32);
+        assert_eq!(
+            generate_raw(&key, 32, Encoding::Base64Url).unwrap().len(),
+            43
+        );
+        assert_eq!(generate_raw(&key, 32, Encoding::Z85).unwrap().len(), 40);
+        assert!(generate_raw(&key, 30, Encoding::Z85).is_err());
+        assert!(generate_raw(&key, 0, Encoding::Hex).is_err());
+        assert!(generate_raw(&key, MAX_RAW_BYTES + 1, Encoding::Hex).is_err());
+    }
+
+    #[test]
+    fn test_raw_entropy_capped_at_key_size() {
+        assert_eq!(raw_entropy(16), 128.0);
+        assert_eq!(raw_entropy(32), 256.0);
+        assert_eq!(raw_entropy(4096), 256.0);
+    }
+
+    #[test]
+    fn test_check_mnemonic_accepts_generated_phrases() {
+        let key = [42u8; 32];
+        let wordlist = Wordlist::eff_large();
+        let format = MnemonicFormat {
+            separator: ".".to_string(),
+            capitalize: true,
+            digit: true,
+            symbol: true,
+        };
+        let phrase = generate_mnemonic_with_format(&key, &wordlist, 8, &format).unwrap();
+
+        let checks = check_mnemonic(&phrase, &wordlist, &format).unwrap();
+        assert_eq!(checks.len(), 8);
+        assert!(checks.iter().all(|check| check.valid));
+        assert!(
+            mnemonic_mismatches(&key, &phrase, &wordlist, 8, &format)
+                .unwrap()
+                .is_empty()
+        );
+
+        assert!(check_mnemonic("", &wordlist, &format).is_err());
+    }
+
+    #[test]
+    fn test_check_mnemonic_suggests_corrections() {
+        let wordlist = Wordlist::eff_large();
+        let format = MnemonicFormat::default();
+
+        let checks = check_mnemonic("abacus-abodmen-zzzzzzzzz", &wordlist, &format).unwrap();
+        assert!(checks[0].valid);
+        assert!(!checks[1].valid);
+        assert_eq!(checks[1].suggestions[0], "abdomen");
+        assert!(!checks[2].valid);
+        assert!(checks[2].suggestions.is_empty());
+    }
+
+    #[test]
+    fn test_check_mnemonic_rejoins_hyphenated_words() {
+        let wordlist = Wordlist::eff_large();
+        let checks = check_mnemonic(
+            "t-shirt-yo-yo-abacus",
+            &wordlist,
+            &MnemonicFormat::default(),
+        )
+        .unwrap();
+
+        let words: Vec<&str> = checks.iter().map(|check| check.word.as_str()).collect();
+        assert_eq!(words, ["t-shirt", "yo-yo", "abacus"]);
+        assert!(checks.iter().all(|check| check.valid));
+    }
+
+    #[test]
+    fn test_mnemonic_mismatches() {
+        let key = [42u8; 32];
+        let wordlist = Wordlist::eff_large();
+        let format = MnemonicFormat::default();
+        let phrase = generate_mnemonic(&key, 6).unwrap();
+
+        let mut words: Vec<&str> = phrase.split('-').collect();
+        words[2] = if words[2] == "abacus" {
+            "zoom"
+        } else {
+            "abacus"
+        };
+        let altered = words.join("-");
+        assert_eq!(
+            mnemonic_mismatches(&key, &altered, &wordlist, 6, &format).unwrap(),
+            [3]
+        );
+
+        let truncated = words[..5].join("-");
+        assert_eq!(
+            mnemonic_mismatches(&key, &truncated, &wordlist, 6, &format).unwrap(),
+            [3, 6]
+        );
+
+        let spaced = phrase.replace('-', " ");
+        let spaced_format = MnemonicFormat {
+            separator: " ".to_string(),
+            ..MnemonicFormat::default()
+        };
+        assert!(
+            mnemonic_mismatches(&key, &spaced, &wordlist, 6, &spaced_format)
+                .unwrap()
+                .is_empty()
+        );
+    }
 }
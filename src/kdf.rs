@@ -45,6 +45,46 @@ impl Argon2Config {
     }
 }
 
+// A scheme covers everything between the inputs and the printed secret:
+// salt preprocessing here, and the keystream, rejection sampling and
+// encodings in the generators, which are pinned by known-answer tests under
+// v1. Any change to either needs a new variant. The default stays v1 so that
+// existing secrets never change under an upgrade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DerivationScheme {
+    #[default]
+    V1,
+}
+
+impl DerivationScheme {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::V1 => "v1",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "v1" => Some(Self::V1),
+            _ => None,
+        }
+    }
+
+    fn salt(&self, salt_input: &[u8]) -> Zeroizing<Vec<u8>> {
+        match self {
+            Self::V1 => {
+                if salt_input.len() >= MIN_SALT_LEN {
+                    Zeroizing::new(salt_input.to_vec())
+                } else {
+                    let mut hasher = Blake2b512::new();
+                    hasher.update(salt_input);
+                    Zeroizing::new(hasher.finalize().to_vec())
+                }
+            }
+        }
+    }
+}
+
 const OUTPUT_LEN: usize = 32;
 const MIN_SALT_LEN: usize = 16;
 
@@ -52,6 +92,15 @@ pub fn derive_hierarchical(
     master_secret: &[u8],
     layers: &[Zeroizing<String>],
     config: Argon2Config,
+) -> Result<Zeroizing<[u8; OUTPUT_LEN]>> {
+    derive_hierarchical_with_scheme(DerivationScheme::V1, master_secret, layers, config)
+}
+
+pub fn derive_hierarchical_with_scheme(
+    scheme: DerivationScheme,
+    master_secret: &[u8],
+    layers: &[Zeroizing<String>],
+    config: Argon2Config,
 ) -> Result<Zeroizing<[u8; OUTPUT_LEN]>> {
     if layers.is_empty() {
         anyhow::bail!("Layers array cannot be empty");
@@ -70,6 +119,7 @@ pub fn derive_hierarchical(
     let mut current_key = Zeroizing::new([0u8; OUTPUT_LEN]);
 
     derive_single(
+        scheme,
         &argon2,
         master_secret,
         layers[0].as_bytes(),
@@ -79,8 +129,14 @@ pub fn derive_hierarchical(
 
     for (i, layer) in layers[1..].iter().enumerate() {
         let mut next_key = Zeroizing::new([0u8; OUTPUT_LEN]);
-        derive_single(&argon2, &current_key[..], layer.as_bytes(), &mut next_key)
-            .with_context(|| format!("Failed to derive key at layer {}", i + 2))?;
+        derive_single(
+            scheme,
+            &argon2,
+            &current_key[..],
+            layer.as_bytes(),
+            &mut next_key,
+        )
+        .with_context(|| format!("Failed to derive key at layer {}", i + 2))?;
         current_key = next_key;
     }
 
@@ -88,18 +144,13 @@ pub fn derive_hierarchical(
 }
 
 fn derive_single(
+    scheme: DerivationScheme,
     argon2: &Argon2,
     password: &[u8],
     salt_input: &[u8],
     output: &mut [u8; OUTPUT_LEN],
 ) -> Result<()> {
-    let salt: Zeroizing<Vec<u8>> = if salt_input.len() >= MIN_SALT_LEN {
-        Zeroizing::new(salt_input.to_vec())
-    } else {
-        let mut hasher = Blake2b512::new();
-        hasher.update(salt_input);
-        Zeroizing::new(hasher.finalize().to_vec())
-    };
+    let salt = scheme.salt(salt_input);
 
     argon2
         .hash_password_into(password, &salt, output)
@@ -432,4 +483,77 @@ mod tests {
         assert_eq!(first, second);
         assert_eq!(first.as_bytes(), second.as_bytes());
     }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_scheme_default_is_v1() {
+        assert_eq!(DerivationScheme::default(), DerivationScheme::V1);
+        assert_eq!(DerivationScheme::V1.name(), "v1");
+        assert_eq!(
+            DerivationScheme::from_name("v1"),
+            Some(DerivationScheme::V1)
+        );
+        assert_eq!(DerivationScheme::from_name("v2"), None);
+
+        let master = b"test_master_secret";
+        let layers = to_zeroizing_vec(vec!["layer1".to_string()]);
+        let config = Argon2Config {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        };
+
+        let key = derive_hierarchical(master, &layers, config).unwrap();
+        let key_v1 =
+            derive_hierarchical_with_scheme(DerivationScheme::V1, master, &layers, config).unwrap();
+        assert_eq!(key.as_ref(), key_v1.as_ref());
+    }
+
+    #[test]
+    fn test_scheme_v1_known_answer_standard() {
+        let master = b"life";
+        let layers = to_zeroizing_vec(vec![
+            "out".to_string(),
+            "of".to_string(),
+            "balance".to_string(),
+        ]);
+
+        let key = derive_hierarchical_with_scheme(
+            DerivationScheme::V1,
+            master,
+            &layers,
+            Argon2Config::STANDARD,
+        )
+        .unwrap();
+
+        assert_eq!(
+            to_hex(key.as_ref()),
+            "6a0e41d4f5b72c7f7ef6ecdc293420bb030e28d88e69b5693a6c27c5262d4010"
+        );
+    }
+
+    #[test]
+    fn test_scheme_v1_known_answer_long_salt() {
+        let master = "секрет🔑".as_bytes();
+        let layers = to_zeroizing_vec(vec![
+            "0802BDCD52656EE9".to_string(),
+            "Dessau-Roßlau".to_string(),
+        ]);
+        let config = Argon2Config {
+            memory_kib: 1024,
+            iterations: 2,
+            parallelism: 2,
+        };
+
+        let key =
+            derive_hierarchical_with_scheme(DerivationScheme::V1, master, &layers, config).unwrap();
+
+        assert_eq!(
+            to_hex(key.as_ref()),
+            "568e542d9146b9ebb926954b42391b0c0e20d2e793dcd529d8b0d0d9d7f52f9b"
+        );
+    }
 }
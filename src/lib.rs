@@ -15,10 +15,27 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+pub mod age;
+pub mod bench;
+pub mod bip39;
+pub mod clipboard;
+pub mod fingerprint;
 pub mod generator;
 pub mod kdf;
+pub mod selftest;
+pub mod shamir;
+pub mod ssh;
+pub mod strength;
+pub mod totp;
+pub mod verify;
+pub mod wireguard;
 pub mod wordlist;
 
-pub use generator::{generate_mnemonic, generate_password};
-pub use kdf::{Argon2Config, derive_hierarchical};
-pub use wordlist::{get_wordlist, wordlist_size};
+pub use generator::{
+    MnemonicFormat, PasswordPolicy, generate_mnemonic, generate_mnemonic_with_format,
+    generate_password, generate_password_with_policy,
+};
+pub use kdf::{
+    Argon2Config, DerivationScheme, derive_hierarchical, derive_hierarchical_with_scheme,
+};
+pub use wordlist::{Wordlist, get_wordlist};
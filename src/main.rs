@@ -15,13 +15,17 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-mod generator;
-mod kdf;
 mod ui;
-mod wordlist;
 
-use anyhow::Result;
-use clap::{Parser, ValueEnum};
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use qatsi::{
+    age, bench, bip39, clipboard, fingerprint, generator, kdf, selftest, shamir, ssh, strength,
+    totp, verify, wireguard, wordlist,
+};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
 
 #[derive(Parser)]
 #[command(
@@ -36,12 +40,15 @@ use clap::{Parser, ValueEnum};
 "
 )]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(
         short,
         long,
         value_enum,
         default_value = "mnemonic",
-        help = "Output mode: a mnemonic phrase or a random password"
+        help = "Output mode: a mnemonic phrase, a random password, a pronounceable password, a numeric PIN, a BIP39 mnemonic, Shamir shares of the key, an SSH key, an age identity, a WireGuard key pair, a TOTP seed or raw key material"
     )]
     mode: Mode,
 
@@ -49,34 +56,471 @@ struct Cli {
         short,
         long,
         value_enum,
+        global = true,
         default_value = "standard",
         help = "Security preset for KDF parameters and output length"
     )]
     security: SecurityLevel,
 
-    #[arg(long, value_name = "COUNT", help = "Override mnemonic word count")]
+    #[arg(
+        long,
+        value_name = "COUNT",
+        global = true,
+        help = "Override mnemonic word count"
+    )]
     words: Option<usize>,
 
-    #[arg(long, value_name = "LENGTH", help = "Override password length")]
+    #[arg(long, value_name = "LENGTH", help = "Override password or PIN length")]
     length: Option<usize>,
 
-    #[arg(long, value_name = "MIB", help = "Override KDF memory cost")]
+    #[arg(
+        long,
+        value_name = "NAME|PATH",
+        global = true,
+        help = "Mnemonic wordlist: eff-large (default), eff-short-1, eff-short-2, or a file pinned with --wordlist-sha256"
+    )]
+    wordlist: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "HEX",
+        global = true,
+        requires = "wordlist",
+        help = "Expected SHA-256 of the wordlist file"
+    )]
+    wordlist_sha256: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "TEXT",
+        global = true,
+        default_value = "-",
+        help = "Separator between mnemonic words"
+    )]
+    separator: String,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Add a keystream-chosen digit to a keystream-chosen mnemonic word"
+    )]
+    add_digit: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Add a keystream-chosen symbol to a keystream-chosen mnemonic word"
+    )]
+    add_symbol: bool,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        default_value_t = 2,
+        help = "Number of Shamir shares required to recover the key"
+    )]
+    threshold: usize,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        default_value_t = 3,
+        help = "Number of Shamir shares to produce"
+    )]
+    shares: usize,
+
+    #[arg(
+        long,
+        value_name = "TEXT",
+        default_value = ssh::DEFAULT_COMMENT,
+        help = "Comment stored in the SSH key"
+    )]
+    comment: String,
+
+    #[arg(
+        long,
+        help = "Encrypt the SSH private key with a passphrase (prompted twice)"
+    )]
+    passphrase: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write the SSH private key to PATH (mode 0600) and the public key to PATH.pub"
+    )]
+    key_file: Option<PathBuf>,
+
+    #[arg(long, help = "Emit the WireGuard key as a ready [Interface] stanza")]
+    interface: bool,
+
+    #[arg(
+        long,
+        value_name = "CIDR",
+        requires = "interface",
+        help = "Address line for the WireGuard [Interface] stanza"
+    )]
+    address: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PORT",
+        requires = "interface",
+        help = "ListenPort line for the WireGuard [Interface] stanza"
+    )]
+    listen_port: Option<u16>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "TOTP issuer for the otpauth URI (defaults to the first layer)"
+    )]
+    issuer: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "TOTP account for the otpauth URI (defaults to the second layer)"
+    )]
+    account: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "BYTES",
+        global = true,
+        default_value_t = totp::DEFAULT_SECRET_BYTES,
+        help = "TOTP secret length"
+    )]
+    secret_bytes: usize,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        global = true,
+        default_value_t = totp::DEFAULT_DIGITS,
+        help = "TOTP code length"
+    )]
+    digits: u32,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        global = true,
+        default_value_t = totp::DEFAULT_PERIOD,
+        help = "TOTP time step"
+    )]
+    period: u64,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Override pronounceable password syllable count"
+    )]
+    syllables: Option<usize>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Capitalize mnemonic words, or one keystream-chosen syllable of pronounceable passwords"
+    )]
+    capitalize: bool,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        default_value_t = 0,
+        help = "Append COUNT keystream-chosen digits to pronounceable passwords"
+    )]
+    append_digits: usize,
+
+    #[arg(long, help = "Skip PINs made of repeats, digit sequences or dates")]
+    reject_weak_pins: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "hex",
+        help = "Text encoding for raw key material"
+    )]
+    encoding: RawEncoding,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        default_value_t = 32,
+        help = "Number of raw key bytes to produce"
+    )]
+    bytes: usize,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write raw key material as a binary keyfile (mode 0600) instead of printing it"
+    )]
+    output_file: Option<PathBuf>,
+
+    #[arg(long, help = "Exclude uppercase letters from passwords")]
+    no_uppercase: bool,
+
+    #[arg(long, help = "Exclude lowercase letters from passwords")]
+    no_lowercase: bool,
+
+    #[arg(long, help = "Exclude digits from passwords")]
+    no_digits: bool,
+
+    #[arg(long, help = "Exclude symbols from passwords")]
+    no_symbols: bool,
+
+    #[arg(
+        long,
+        value_name = "CHARS",
+        help = "Use a custom password alphabet of printable ASCII characters"
+    )]
+    alphabet: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "CHARS",
+        help = "Exclude specific characters from passwords"
+    )]
+    exclude: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        default_value_t = 0,
+        help = "Require at least COUNT characters from each available class"
+    )]
+    min_per_class: usize,
+
+    #[arg(
+        long,
+        help = "Require at least one character from each available class"
+    )]
+    require_classes: bool,
+
+    #[arg(long, help = "Disallow identical adjacent characters in passwords")]
+    no_repeats: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        global = true,
+        default_value = "v1",
+        help = "Derivation scheme version used to reproduce secrets"
+    )]
+    scheme: Scheme,
+
+    #[arg(
+        long,
+        value_name = "MIB",
+        global = true,
+        help = "Override KDF memory cost"
+    )]
     kdf_memory: Option<u32>,
 
-    #[arg(long, value_name = "COUNT", help = "Override KDF iterations")]
+    #[arg(
+        long,
+        value_name = "COUNT",
+        global = true,
+        help = "Override KDF iterations"
+    )]
     kdf_iterations: Option<u32>,
 
-    #[arg(long, value_name = "LANES", help = "Override KDF parallelism")]
+    #[arg(
+        long,
+        value_name = "LANES",
+        global = true,
+        help = "Override KDF parallelism"
+    )]
     kdf_parallelism: Option<u32>,
 
-    #[arg(long, help = "Disable Unicode output")]
+    #[arg(long, global = true, help = "Disable Unicode output")]
     no_unicode: bool,
 
-    #[arg(long, help = "Disable colored output")]
+    #[arg(long, global = true, help = "Disable colored output")]
     no_color: bool,
 
     #[arg(short, long, help = "Suppress settings and statistics output")]
     quiet: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "text",
+        help = "Output format: human-readable text or JSON"
+    )]
+    format: Format,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["output_file", "key_file"],
+        help = "Copy the output to the clipboard instead of printing it, then clear it"
+    )]
+    clip: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        requires = "clip",
+        default_value_t = clipboard::DEFAULT_CLEAR_SECONDS,
+        help = "Time before the clipboard is cleared or restored"
+    )]
+    clip_timeout: u64,
+
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        requires = "clip",
+        help = "Command that reads the clipboard contents from stdin (e.g. \"wl-copy\")"
+    )]
+    clip_command: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        requires = "clip_command",
+        help = "Command that prints the clipboard contents, used to restore them (e.g. \"wl-paste --no-newline\")"
+    )]
+    clip_paste_command: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "FD",
+        global = true,
+        help = "Read the master secret from a file descriptor instead of prompting"
+    )]
+    master_fd: Option<i32>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        global = true,
+        help = "Read newline- or NUL-delimited layers from a file instead of prompting"
+    )]
+    layers_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "WORDS",
+        global = true,
+        help = "Abort unless the master secret has this three-word fingerprint"
+    )]
+    expect_fingerprint: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        conflicts_with = "master_fd",
+        help = "Ask for the master secret twice before deriving"
+    )]
+    confirm: bool,
+
+    #[arg(
+        long,
+        value_name = "BITS",
+        global = true,
+        default_value_t = ui::MIN_MASTER_STRENGTH_BITS,
+        help = "Estimated master secret strength below which it is reported as weak"
+    )]
+    min_master_strength: f64,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Abort instead of warning when the master secret is estimated as weak"
+    )]
+    refuse_weak_master: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Verify built-in known-answer test vectors on this machine")]
+    Selftest {
+        #[arg(
+            long,
+            help = "Run all vectors, including slow Standard and Paranoid ones"
+        )]
+        full: bool,
+    },
+
+    #[command(about = "Measure KDF cost on this machine and recommend parameters")]
+    Bench {
+        #[arg(
+            long,
+            value_name = "MS",
+            default_value_t = 2000,
+            help = "Target latency for a full derivation"
+        )]
+        target_ms: u64,
+
+        #[arg(
+            long,
+            value_name = "COUNT",
+            default_value_t = 3,
+            help = "Number of layers in a typical derivation"
+        )]
+        layers: usize,
+
+        #[arg(
+            long,
+            value_name = "MIB",
+            value_delimiter = ',',
+            help = "Memory costs to measure (comma-separated)"
+        )]
+        memory: Vec<u32>,
+
+        #[arg(
+            long,
+            value_name = "COUNT",
+            value_delimiter = ',',
+            help = "Iteration counts to measure (comma-separated)"
+        )]
+        iterations: Vec<u32>,
+
+        #[arg(
+            long,
+            value_name = "LANES",
+            value_delimiter = ',',
+            help = "Parallelism values to measure (comma-separated)"
+        )]
+        parallelism: Vec<u32>,
+    },
+
+    #[command(about = "Compute the current TOTP code for a derived seed")]
+    Totp {
+        #[arg(
+            long,
+            value_name = "UNIX_TIME",
+            help = "Compute the code for a specific time instead of now"
+        )]
+        at: Option<u64>,
+    },
+
+    #[command(about = "Recover and verify a derived key from Shamir shares")]
+    Combine {
+        #[arg(
+            long,
+            value_name = "PATH",
+            help = "Read newline-delimited shares from a file instead of prompting"
+        )]
+        shares_file: Option<PathBuf>,
+    },
+
+    #[command(
+        about = "Check a written-down mnemonic for typos and confirm it matches a derivation"
+    )]
+    CheckMnemonic {
+        #[arg(
+            long,
+            help = "Only check the words against the wordlist, without deriving"
+        )]
+        no_derive: bool,
+    },
+
+    #[command(
+        about = "Check a remembered secret against a derivation without printing it (generation options go before the subcommand)"
+    )]
+    Verify,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
@@ -84,6 +528,53 @@ struct Cli {
 enum Mode {
     Mnemonic,
     Password,
+    Pronounceable,
+    Pin,
+    Bip39,
+    Shamir,
+    #[value(name = "ssh-ed25519")]
+    SshEd25519,
+    Age,
+    #[value(name = "wireguard")]
+    WireGuard,
+    #[value(name = "totp-seed")]
+    TotpSeed,
+    Raw,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "lowercase")]
+enum RawEncoding {
+    Hex,
+    Base32,
+    Base58,
+    Base64url,
+    Z85,
+}
+
+impl RawEncoding {
+    fn encoding(self) -> generator::Encoding {
+        match self {
+            RawEncoding::Hex => generator::Encoding::Hex,
+            RawEncoding::Base32 => generator::Encoding::Base32,
+            RawEncoding::Base58 => generator::Encoding::Base58,
+            RawEncoding::Base64url => generator::Encoding::Base64Url,
+            RawEncoding::Z85 => generator::Encoding::Z85,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "lowercase")]
+enum Scheme {
+    V1,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "lowercase")]
+enum Format {
+    Text,
+    Json,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
@@ -108,16 +599,500 @@ fn main() -> Result<()> {
         ui::detect_unicode_support()
     };
 
-    let (master_secret, master_byte_length, master_char_count) = ui::prompt_master_secret()?;
+    // Reading the master from stdin consumes it, leaving nothing for layer prompts
+    if cli.master_fd == Some(0) && cli.layers_file.is_none() {
+        anyhow::bail!("--master-fd 0 reads stdin to the end, so it requires --layers-file");
+    }
+
+    let clip = if cli.clip {
+        if cli.command.is_some() {
+            anyhow::bail!("--clip only applies to generated secrets, not to subcommands");
+        }
+        if cli.format == Format::Json {
+            anyhow::bail!("--clip cannot be combined with --format json");
+        }
+        if cli.clip_timeout == 0 {
+            anyhow::bail!("Clipboard timeout must be at least 1 second");
+        }
+
+        let clipboard = match &cli.clip_command {
+            Some(command) => {
+                clipboard::Clipboard::from_commands(command, cli.clip_paste_command.as_deref())?
+            }
+            None => clipboard::Clipboard::detect()?,
+        };
+        Some(ui::ClipOptions {
+            clipboard,
+            timeout: Duration::from_secs(cli.clip_timeout),
+        })
+    } else {
+        None
+    };
+
+    let display_options = ui::DisplayOptions {
+        unicode_support,
+        color_support,
+        quiet: cli.quiet,
+        json: cli.format == Format::Json,
+        clip,
+    };
+
+    if let Some(command) = &cli.command {
+        return match command {
+            Command::Selftest { full } => run_selftest(*full, &display_options),
+            Command::Bench {
+                target_ms,
+                layers,
+                memory,
+                iterations,
+                parallelism,
+            } => {
+                let or_default = |values: &[u32], default: &[u32]| {
+                    if values.is_empty() {
+                        default.to_vec()
+                    } else {
+                        values.to_vec()
+                    }
+                };
+
+                run_bench(
+                    &or_default(memory, bench::DEFAULT_MEMORY_MIB),
+                    &or_default(iterations, bench::DEFAULT_ITERATIONS),
+                    &or_default(parallelism, bench::DEFAULT_PARALLELISM),
+                    *layers,
+                    Duration::from_millis(*target_ms),
+                    ui::kdf_floor(cli.security == SecurityLevel::Paranoid),
+                    &display_options,
+                )
+            }
+            Command::Combine { shares_file } => {
+                run_combine(shares_file.as_deref(), &display_options)
+            }
+            Command::Totp { at } => run_totp(&cli, *at, &display_options),
+            Command::CheckMnemonic { no_derive } => {
+                run_check_mnemonic(&cli, *no_derive, &display_options)
+            }
+            Command::Verify => run_verify(&cli, &display_options),
+        };
+    }
+
+    // Checked before any prompt so a typo does not cost a full derivation
+    if let Some(address) = &cli.address {
+        wireguard::check_address(address)?;
+    }
+
+    let (master_secret, layers, input_info) = read_inputs(&cli, &display_options)?;
+    let (output, info, out_cfg, kdf_cfg, elapsed) =
+        generate(&cli, &master_secret, &layers, input_info, &display_options)?;
+
+    ui::display_output(
+        &output,
+        &info,
+        &out_cfg,
+        &kdf_cfg,
+        derivation_scheme(&cli),
+        elapsed,
+        &display_options,
+    )
+}
+
+type Generated = (
+    Zeroizing<String>,
+    ui::InputInfo,
+    ui::OutputConfig,
+    kdf::Argon2Config,
+    Duration,
+);
+
+fn generate(
+    cli: &Cli,
+    master_secret: &[u8],
+    layers: &[Zeroizing<String>],
+    input_info: ui::InputInfo,
+    options: &ui::DisplayOptions,
+) -> Result<Generated> {
+    let kdf_config = kdf_config(cli);
+    let scheme = derivation_scheme(cli);
+
+    let (default_words, default_length, default_syllables, default_pin_length, default_bip39_words) =
+        match cli.security {
+            SecurityLevel::Standard => (8, 20, 16, 6, 12),
+            SecurityLevel::Paranoid => (24, 48, 48, 8, 24),
+        };
+
+    let password_policy = generator::PasswordPolicy {
+        uppercase: !cli.no_uppercase,
+        lowercase: !cli.no_lowercase,
+        digits: !cli.no_digits,
+        symbols: !cli.no_symbols,
+        alphabet: cli.alphabet.clone(),
+        exclude: cli.exclude.clone().unwrap_or_default(),
+        min_per_class: if cli.require_classes {
+            cli.min_per_class.max(1)
+        } else {
+            cli.min_per_class
+        },
+        no_repeats: cli.no_repeats,
+    };
+
+    if cli.wordlist.is_some() && cli.mode != Mode::Mnemonic {
+        anyhow::bail!("--wordlist is only supported with --mode mnemonic");
+    }
+    let mnemonic_wordlist = mnemonic_wordlist(cli)?;
+    let mnemonic_format = mnemonic_format(cli);
+
+    let totp_params = totp::TotpParams {
+        secret_bytes: cli.secret_bytes,
+        digits: cli.digits,
+        period: cli.period,
+    };
+
+    let issuer = match &cli.issuer {
+        Some(issuer) => issuer.clone(),
+        None => layers
+            .first()
+            .map(|layer| layer.to_string())
+            .unwrap_or_default(),
+    };
+    let account = match &cli.account {
+        Some(account) => account.clone(),
+        None => layers
+            .get(1)
+            .map(|layer| layer.to_string())
+            .unwrap_or_default(),
+    };
+
+    if cli.mode == Mode::TotpSeed && account.is_empty() {
+        anyhow::bail!("No TOTP account: pass --account or provide at least two layers");
+    }
+
+    let output_config = match cli.mode {
+        Mode::Mnemonic => {
+            let word_count = cli.words.unwrap_or(default_words);
+            mnemonic_format.check()?;
+            mnemonic_format.check_word_count(word_count)?;
+            ui::OutputConfig {
+                word_count,
+                password_length: 0,
+                wordlist_size: mnemonic_wordlist.len(),
+                charset_size: 0,
+                entropy: generator::mnemonic_entropy(word_count, mnemonic_wordlist.len())
+                    + mnemonic_format.added_entropy(),
+                kind: ui::OutputKind::Mnemonic {
+                    digit: mnemonic_format.digit,
+                    symbol: mnemonic_format.symbol,
+                    wordlist: mnemonic_wordlist.kind(),
+                },
+                public: None,
+            }
+        }
+        Mode::Password => {
+            let password_length = cli.length.unwrap_or(default_length);
+            ui::OutputConfig {
+                word_count: 0,
+                password_length,
+                wordlist_size: 0,
+                charset_size: password_policy.effective_alphabet()?.len(),
+                entropy: password_policy.entropy(password_length)?,
+                kind: ui::OutputKind::Password,
+                public: None,
+            }
+        }
+        Mode::Pronounceable => {
+            let options = generator::PronounceableOptions {
+                syllables: cli.syllables.unwrap_or(default_syllables),
+                capitalize: cli.capitalize,
+                digits: cli.append_digits,
+            };
+            options.check()?;
+            ui::OutputConfig {
+                word_count: 0,
+                password_length: options.syllables * 2 + options.digits,
+                wordlist_size: 0,
+                charset_size: 0,
+                entropy: options.entropy(),
+                kind: ui::OutputKind::Pronounceable(options),
+                public: None,
+            }
+        }
+        Mode::Pin => {
+            let pin_length = cli.length.unwrap_or(default_pin_length);
+            generator::check_pin_length(pin_length)?;
+            ui::OutputConfig {
+                word_count: 0,
+                password_length: pin_length,
+                wordlist_size: 0,
+                charset_size: 10,
+                entropy: generator::pin_entropy(pin_length, cli.reject_weak_pins),
+                kind: ui::OutputKind::Pin {
+                    reject_weak: cli.reject_weak_pins,
+                },
+                public: None,
+            }
+        }
+        Mode::Bip39 => {
+            let word_count = cli.words.unwrap_or(default_bip39_words);
+            bip39::check_word_count(word_count)?;
+            ui::OutputConfig {
+                word_count,
+                password_length: 0,
+                wordlist_size: bip39::bip39_wordlist_size() as usize,
+                charset_size: 0,
+                entropy: bip39::bip39_entropy(word_count),
+                kind: ui::OutputKind::Bip39,
+                public: None,
+            }
+        }
+        Mode::Shamir => {
+            shamir::check_parameters(cli.threshold, cli.shares)?;
+            ui::OutputConfig {
+                word_count: 0,
+                password_length: 0,
+                wordlist_size: 0,
+                charset_size: 0,
+                entropy: 256.0,
+                kind: ui::OutputKind::Shamir {
+                    threshold: cli.threshold,
+                    share_count: cli.shares,
+                },
+                public: None,
+            }
+        }
+        Mode::SshEd25519 => ui::OutputConfig {
+            word_count: 0,
+            password_length: 0,
+            wordlist_size: 0,
+            charset_size: 0,
+            entropy: 256.0,
+            kind: ui::OutputKind::SshEd25519 {
+                encrypted: cli.passphrase,
+            },
+            public: None,
+        },
+        Mode::Age => ui::OutputConfig {
+            word_count: 0,
+            password_length: 0,
+            wordlist_size: 0,
+            charset_size: 0,
+            entropy: 256.0,
+            kind: ui::OutputKind::Age,
+            public: None,
+        },
+        Mode::TotpSeed => {
+            totp_params.check()?;
+            ui::OutputConfig {
+                word_count: 0,
+                password_length: 0,
+                wordlist_size: 0,
+                charset_size: 0,
+                entropy: (totp_params.secret_bytes * 8) as f64,
+                kind: ui::OutputKind::TotpSeed {
+                    secret_bytes: totp_params.secret_bytes,
+                    digits: totp_params.digits,
+                    period: totp_params.period,
+                },
+                public: None,
+            }
+        }
+        Mode::Raw => {
+            generator::check_raw_length(cli.bytes)?;
+            let encoding = match cli.output_file {
+                Some(_) => None,
+                None => Some(cli.encoding.encoding()),
+            };
+            if let Some(encoding) = encoding {
+                encoding.check_length(cli.bytes)?;
+            }
+            ui::OutputConfig {
+                word_count: 0,
+                password_length: 0,
+                wordlist_size: 0,
+                charset_size: 0,
+                entropy: generator::raw_entropy(cli.bytes),
+                kind: ui::OutputKind::Raw {
+                    bytes: cli.bytes,
+                    encoding,
+                },
+                public: None,
+            }
+        }
+        Mode::WireGuard => ui::OutputConfig {
+            word_count: 0,
+            password_length: 0,
+            wordlist_size: 0,
+            charset_size: 0,
+            entropy: 251.0,
+            kind: ui::OutputKind::WireGuard {
+                interface: cli.interface,
+            },
+            public: None,
+        },
+    };
+
+    let interface_options = wireguard::InterfaceOptions {
+        address: cli.address.clone(),
+        listen_port: cli.listen_port,
+    };
 
-    let (layers, layer_infos) = ui::prompt_layers()?;
+    if let Some(path) = &cli.key_file {
+        if cli.mode != Mode::SshEd25519 {
+            anyhow::bail!("--key-file is only supported with --mode ssh-ed25519");
+        }
+        ui::check_key_files_absent(path)?;
+    }
+
+    if let Some(path) = &cli.output_file {
+        if cli.mode != Mode::Raw {
+            anyhow::bail!("--output-file is only supported with --mode raw");
+        }
+        ui::check_output_file_absent(path)?;
+    }
+
+    let passphrase = if cli.mode == Mode::SshEd25519 && cli.passphrase {
+        Some(ui::prompt_passphrase()?)
+    } else {
+        None
+    };
+
+    let ((output, info, out_cfg, kdf_cfg), elapsed) = ui::show_progress(options, || {
+        let final_key =
+            kdf::derive_hierarchical_with_scheme(scheme, master_secret, layers, kdf_config)?;
+
+        let mut output_config = output_config;
+        let output = match output_config.kind {
+            ui::OutputKind::Mnemonic { .. } => generator::generate_mnemonic_with_format(
+                &final_key,
+                &mnemonic_wordlist,
+                output_config.word_count,
+                &mnemonic_format,
+            )?,
+            ui::OutputKind::Password => generator::generate_password_with_policy(
+                &final_key,
+                output_config.password_length,
+                &password_policy,
+            )?,
+            ui::OutputKind::Pronounceable(options) => {
+                generator::generate_pronounceable(&final_key, &options)?
+            }
+            ui::OutputKind::Pin { reject_weak } => {
+                generator::generate_pin(&final_key, output_config.password_length, reject_weak)?
+            }
+            ui::OutputKind::Bip39 => bip39::generate_bip39(&final_key, output_config.word_count)?,
+            ui::OutputKind::Shamir {
+                threshold,
+                share_count,
+            } => shamir::generate_shares(&final_key, threshold, share_count)?,
+            ui::OutputKind::SshEd25519 { .. } => {
+                let pair = ssh::generate_ssh_ed25519(
+                    &final_key,
+                    &cli.comment,
+                    passphrase.as_ref().map(|p| p.as_bytes()),
+                )?;
+                output_config.public = Some(ui::PublicPart::SshFingerprint(pair.fingerprint));
+
+                match &cli.key_file {
+                    Some(path) => {
+                        ui::write_key_files(path, &pair.private_key, &pair.public_key)?;
+                        Zeroizing::new(pair.public_key)
+                    }
+                    None => Zeroizing::new(format!("{}{}", &*pair.private_key, pair.public_key)),
+                }
+            }
+            ui::OutputKind::Age => {
+                let identity = age::generate_age_identity(&final_key);
+                output_config.public = Some(ui::PublicPart::AgeRecipient(identity.recipient));
+                identity.contents
+            }
+            ui::OutputKind::TotpSeed { .. } => {
+                totp::otpauth_uri(&final_key, &issuer, &account, &totp_params)?
+            }
+            ui::OutputKind::WireGuard { interface } => {
+                let generated = wireguard::generate_wireguard(
+                    &final_key,
+                    interface.then_some(&interface_options),
+                )?;
+                output_config.public = Some(ui::PublicPart::WireGuardKey(generated.public_key));
+                generated.contents
+            }
+            ui::OutputKind::Raw { bytes, .. } => match &cli.output_file {
+                Some(path) => {
+                    let material = generator::expand_key(&final_key, bytes)?;
+                    ui::write_output_file(path, &material)?;
+                    Zeroizing::new(path.display().to_string())
+                }
+                None => generator::generate_raw(&final_key, bytes, cli.encoding.encoding())?,
+            },
+        };
+
+        Ok((output, input_info, output_config, kdf_config))
+    })?;
+
+    Ok((output, info, out_cfg, kdf_cfg, elapsed))
+}
+
+type DerivationInputs = (Zeroizing<Vec<u8>>, Vec<Zeroizing<String>>, ui::InputInfo);
+
+fn read_inputs(cli: &Cli, options: &ui::DisplayOptions) -> Result<DerivationInputs> {
+    let (master_secret, master_byte_length, master_char_count) = match cli.master_fd {
+        Some(fd) => ui::read_master_secret_fd(fd)?,
+        None => ui::prompt_master_secret(cli.confirm)?,
+    };
+
+    let master_strength = strength::estimate(
+        std::str::from_utf8(&master_secret).context("Master secret is not valid UTF-8")?,
+    );
+    if cli.refuse_weak_master && master_strength.bits < cli.min_master_strength {
+        anyhow::bail!(
+            "Master secret is too weak (~{:.1} bits estimated, {:.0} required)",
+            master_strength.bits,
+            cli.min_master_strength
+        );
+    }
+
+    // In batch mode the fingerprint is neither shown nor needed unless it is
+    // checked, so the extra Argon2 run is skipped
+    let master_fingerprint = if cli.master_fd.is_none() || cli.expect_fingerprint.is_some() {
+        let (master_fingerprint, _) =
+            ui::show_spinner(options, "Computing fingerprint...", || {
+                fingerprint::master_fingerprint(&master_secret)
+            })?;
+        Some(master_fingerprint)
+    } else {
+        None
+    };
+    if let Some(master_fingerprint) = &master_fingerprint {
+        if cli.master_fd.is_none() {
+            ui::display_master_fingerprint(master_fingerprint);
+        }
+        if let Some(expected) = &cli.expect_fingerprint
+            && !fingerprint::fingerprint_matches(master_fingerprint, expected)
+        {
+            anyhow::bail!(
+                "Master secret fingerprint {} does not match the expected {}",
+                master_fingerprint,
+                expected
+            );
+        }
+    }
+
+    let (layers, layer_infos) = match &cli.layers_file {
+        Some(path) => ui::read_layers_file(path)?,
+        None => ui::prompt_layers()?,
+    };
 
     let input_info = ui::InputInfo {
         master_byte_length,
         master_char_count,
+        master_fingerprint,
+        master_strength,
+        min_master_strength: cli.min_master_strength,
         layers: layer_infos,
     };
 
+    Ok((master_secret, layers, input_info))
+}
+
+fn kdf_config(cli: &Cli) -> kdf::Argon2Config {
     let mut kdf_config = match cli.security {
         SecurityLevel::Standard => kdf::Argon2Config::STANDARD,
         SecurityLevel::Paranoid => kdf::Argon2Config::PARANOID,
@@ -133,54 +1108,257 @@ fn main() -> Result<()> {
         kdf_config.parallelism = par;
     }
 
-    let (default_words, default_length) = match cli.security {
-        SecurityLevel::Standard => (8, 20),
-        SecurityLevel::Paranoid => (24, 48),
+    kdf_config
+}
+
+fn derivation_scheme(cli: &Cli) -> kdf::DerivationScheme {
+    match cli.scheme {
+        Scheme::V1 => kdf::DerivationScheme::V1,
+    }
+}
+
+fn mnemonic_wordlist(cli: &Cli) -> Result<wordlist::Wordlist> {
+    let Some(selector) = &cli.wordlist else {
+        return Ok(wordlist::Wordlist::eff_large());
     };
 
-    let output_config = match cli.mode {
-        Mode::Mnemonic => ui::OutputConfig {
-            word_count: cli.words.unwrap_or(default_words),
-            password_length: 0,
-            wordlist_size: 7776,
-            charset_size: 0,
-            is_mnemonic: true,
-        },
-        Mode::Password => ui::OutputConfig {
-            word_count: 0,
-            password_length: cli.length.unwrap_or(default_length),
-            wordlist_size: 0,
-            charset_size: 90,
-            is_mnemonic: false,
-        },
+    match (
+        wordlist::WordlistKind::from_id(selector),
+        &cli.wordlist_sha256,
+    ) {
+        (Some(kind), None) => wordlist::Wordlist::builtin(kind),
+        (Some(_), Some(_)) => anyhow::bail!("--wordlist-sha256 only applies to wordlist files"),
+        (None, Some(sha256)) => ui::read_wordlist_file(Path::new(selector), sha256),
+        (None, None) => anyhow::bail!(
+            "Unknown wordlist \"{}\"; a wordlist file must be pinned with --wordlist-sha256",
+            selector
+        ),
+    }
+}
+
+fn mnemonic_format(cli: &Cli) -> generator::MnemonicFormat {
+    generator::MnemonicFormat {
+        separator: cli.separator.clone(),
+        capitalize: cli.capitalize,
+        digit: cli.add_digit,
+        symbol: cli.add_symbol,
+    }
+}
+
+fn run_totp(cli: &Cli, at: Option<u64>, options: &ui::DisplayOptions) -> Result<()> {
+    let params = totp::TotpParams {
+        secret_bytes: cli.secret_bytes,
+        digits: cli.digits,
+        period: cli.period,
     };
+    params.check()?;
 
-    let ((output, info, out_cfg, kdf_cfg), elapsed) = ui::show_progress(unicode_support, || {
-        let final_key = kdf::derive_hierarchical(&master_secret, &layers, kdf_config)?;
+    let (master_secret, layers, _) = read_inputs(cli, options)?;
+    let kdf_config = kdf_config(cli);
+    let scheme = derivation_scheme(cli);
 
-        if output_config.is_mnemonic {
-            let mnemonic = generator::generate_mnemonic(&final_key, output_config.word_count)?;
-            Ok((mnemonic, input_info, output_config, kdf_config))
-        } else {
-            let password = generator::generate_password(&final_key, output_config.password_length)?;
-            Ok((password, input_info, output_config, kdf_config))
-        }
+    let (secret, _) = ui::show_progress(options, || {
+        let final_key =
+            kdf::derive_hierarchical_with_scheme(scheme, &master_secret, &layers, kdf_config)?;
+        totp::totp_secret(&final_key, &params)
     })?;
 
-    let display_options = ui::DisplayOptions {
-        unicode_support,
-        color_support,
-        quiet: cli.quiet,
+    let now = match at {
+        Some(time) => time,
+        None => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("System clock is before the Unix epoch")?
+            .as_secs(),
     };
 
-    ui::display_output(
-        &output,
-        &info,
-        &out_cfg,
-        &kdf_cfg,
-        elapsed,
-        &display_options,
+    let code = totp::totp(&secret, now, &params)?;
+    ui::display_totp_code(
+        code,
+        params.digits,
+        totp::seconds_remaining(now, &params),
+        options,
     );
 
     Ok(())
 }
+
+fn run_check_mnemonic(cli: &Cli, no_derive: bool, options: &ui::DisplayOptions) -> Result<()> {
+    let wordlist = mnemonic_wordlist(cli)?;
+    let format = mnemonic_format(cli);
+
+    let phrase = ui::prompt_mnemonic()?;
+    let checks = generator::check_mnemonic(&phrase, &wordlist, &format)?;
+    ui::display_mnemonic_check(&checks, wordlist.kind().name(), options);
+
+    let invalid = checks.iter().filter(|check| !check.valid).count();
+    if invalid > 0 {
+        anyhow::bail!(
+            "{} of {} words are not in the wordlist",
+            invalid,
+            checks.len()
+        );
+    }
+    if no_derive {
+        return Ok(());
+    }
+
+    let word_count = cli.words.unwrap_or(checks.len());
+    let (master_secret, layers, _) = read_inputs(cli, options)?;
+    let kdf_config = kdf_config(cli);
+    let scheme = derivation_scheme(cli);
+
+    let (mismatches, _) = ui::show_progress(options, || {
+        let final_key =
+            kdf::derive_hierarchical_with_scheme(scheme, &master_secret, &layers, kdf_config)?;
+        generator::mnemonic_mismatches(&final_key, &phrase, &wordlist, word_count, &format)
+    })?;
+
+    ui::display_mnemonic_match(&mismatches, options);
+
+    if !mismatches.is_empty() {
+        anyhow::bail!("Mnemonic does not match the derivation");
+    }
+
+    Ok(())
+}
+
+fn run_verify(cli: &Cli, options: &ui::DisplayOptions) -> Result<()> {
+    if matches!(
+        cli.mode,
+        Mode::Shamir | Mode::SshEd25519 | Mode::Age | Mode::WireGuard
+    ) {
+        anyhow::bail!("verify only supports single-line outputs");
+    }
+    if cli.output_file.is_some() {
+        anyhow::bail!("--output-file cannot be used with verify");
+    }
+
+    let candidate = ui::prompt_candidate()?;
+    let (master_secret, layers, input_info) = read_inputs(cli, options)?;
+    let (output, ..) = generate(cli, &master_secret, &layers, input_info, options)?;
+
+    let matched = verify::constant_time_eq(output.as_bytes(), candidate.as_bytes());
+    ui::display_verify_result(matched, options);
+
+    if !matched {
+        anyhow::bail!("Candidate does not match the derivation");
+    }
+
+    Ok(())
+}
+
+fn run_selftest(full: bool, options: &ui::DisplayOptions) -> Result<()> {
+    let vectors: Vec<_> = selftest::test_vectors()?
+        .into_iter()
+        .filter(|vector| full || vector.fast)
+        .collect();
+
+    let mut failures = 0;
+
+    for vector in &vectors {
+        let start = Instant::now();
+        let result = selftest::verify_vector(vector);
+        ui::display_selftest_result(&vector.name, &result, start.elapsed(), options);
+
+        if result.is_err() {
+            failures += 1;
+        }
+    }
+
+    ui::display_selftest_summary(vectors.len(), failures, options);
+
+    if failures > 0 {
+        anyhow::bail!(
+            "Self-test failed ({} of {} vectors)",
+            failures,
+            vectors.len()
+        );
+    }
+
+    Ok(())
+}
+
+fn run_combine(shares_file: Option<&Path>, options: &ui::DisplayOptions) -> Result<()> {
+    let inputs = match shares_file {
+        Some(path) => ui::read_shares_file(path)?,
+        None => ui::prompt_shares()?,
+    };
+
+    let shares = inputs
+        .iter()
+        .enumerate()
+        .map(|(i, input)| {
+            shamir::Share::parse(input).with_context(|| format!("Share {} is invalid", i + 1))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let key = shamir::combine_shares(&shares)?;
+
+    ui::display_combined_key(
+        &shamir::format_key(&key),
+        shares[0].threshold,
+        shares.len(),
+        &shamir::format_fingerprint(&shares[0].fingerprint),
+        options,
+    );
+
+    Ok(())
+}
+
+fn run_bench(
+    memory_mib: &[u32],
+    iterations: &[u32],
+    parallelism: &[u32],
+    layers: usize,
+    target: Duration,
+    floor: kdf::Argon2Config,
+    options: &ui::DisplayOptions,
+) -> Result<()> {
+    if layers == 0 {
+        anyhow::bail!("Layer count must be at least 1");
+    }
+
+    if options.json {
+        anyhow::bail!("bench does not support --format json");
+    }
+
+    // A recommendation must also pass the KDF check shown after derivation,
+    // which holds configurations from 64 MiB up to the Paranoid floor
+    let configs = bench::grid(memory_mib, iterations, parallelism, |config| {
+        bench::meets_floor(config, &floor) && ui::kdf_is_secure(config)
+    })?;
+    if configs.is_empty() {
+        anyhow::bail!("No configuration in the grid meets the KDF floor");
+    }
+
+    ui::display_bench_header(&floor, configs.len(), layers, target);
+
+    let mut samples = Vec::with_capacity(configs.len());
+    let mut too_slow: Option<(u32, u64)> = None;
+
+    for config in configs {
+        if let Some((lanes, cost)) = too_slow
+            && config.parallelism == lanes
+            && bench::cost(&config) >= cost
+        {
+            ui::display_bench_skipped(&config);
+            continue;
+        }
+
+        let sample = bench::BenchSample {
+            config,
+            per_layer: bench::measure_layer(config)?,
+        };
+        ui::display_bench_sample(&sample, layers, target, options);
+
+        if sample.total(layers) > target * 2 {
+            too_slow = Some((config.parallelism, bench::cost(&config)));
+        }
+        samples.push(sample);
+    }
+
+    let recommended = bench::recommend(&samples, layers, target);
+    ui::display_bench_recommendation(recommended.as_ref(), &floor, layers, options);
+
+    Ok(())
+}
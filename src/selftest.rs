@@ -0,0 +1,213 @@
+// This file is part of Qatsi.
+//
+// Copyright (c) 2025  René Coignard <contact@renecoignard.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::bip39::generate_bip39;
+use crate::generator::{generate_mnemonic, generate_password};
+use crate::kdf::{Argon2Config, DerivationScheme, derive_hierarchical_with_scheme};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use zeroize::Zeroizing;
+
+const VECTORS_DATA: &str = include_str!("../assets/test_vectors.json");
+const VECTORS_VERSION: u32 = 1;
+
+#[derive(Deserialize)]
+struct VectorFile {
+    version: u32,
+    vectors: Vec<TestVector>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TestVector {
+    pub name: String,
+    pub fast: bool,
+    pub scheme: String,
+    pub kdf: VectorKdf,
+    pub master: String,
+    pub layers: Vec<String>,
+    pub key: String,
+    pub outputs: Vec<VectorOutput>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VectorKdf {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VectorOutput {
+    pub mode: String,
+    pub count: usize,
+    pub value: String,
+}
+
+pub fn test_vectors() -> Result<Vec<TestVector>> {
+    let file: VectorFile =
+        serde_json::from_str(VECTORS_DATA).context("Failed to parse test vectors")?;
+
+    if file.version != VECTORS_VERSION {
+        anyhow::bail!(
+            "Unsupported test vector version {} (expected {})",
+            file.version,
+            VECTORS_VERSION
+        );
+    }
+
+    Ok(file.vectors)
+}
+
+pub fn verify_vector(vector: &TestVector) -> Result<()> {
+    let scheme = DerivationScheme::from_name(&vector.scheme)
+        .with_context(|| format!("Unknown derivation scheme \"{}\"", vector.scheme))?;
+
+    let config = Argon2Config {
+        memory_kib: vector.kdf.memory_kib,
+        iterations: vector.kdf.iterations,
+        parallelism: vector.kdf.parallelism,
+    };
+
+    let layers: Vec<Zeroizing<String>> = vector
+        .layers
+        .iter()
+        .map(|layer| Zeroizing::new(layer.clone()))
+        .collect();
+
+    let key = derive_hierarchical_with_scheme(scheme, vector.master.as_bytes(), &layers, config)?;
+
+    let key_hex: String = key.iter().map(|b| format!("{:02x}", b)).collect();
+    if key_hex != vector.key {
+        anyhow::bail!(
+            "Derived key mismatch (got {}, expected {})",
+            key_hex,
+            vector.key
+        );
+    }
+
+    for output in &vector.outputs {
+        let actual = match output.mode.as_str() {
+            "mnemonic" => generate_mnemonic(&key, output.count)?,
+            "password" => generate_password(&key, output.count)?,
+            "bip39" => generate_bip39(&key, output.count)?,
+            other => anyhow::bail!("Unknown output mode \"{}\"", other),
+        };
+
+        if *actual != output.value {
+            anyhow::bail!(
+                "{} output mismatch ({} {})",
+                output.mode,
+                output.count,
+                if output.mode == "password" {
+                    "chars"
+                } else {
+                    "words"
+                }
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_vectors_parse() {
+        let vectors = test_vectors().unwrap();
+        assert!(!vectors.is_empty());
+
+        let names: HashSet<_> = vectors.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names.len(), vectors.len(), "Vector names must be unique");
+
+        assert!(vectors.iter().any(|v| v.fast));
+        assert!(vectors.iter().any(|v| !v.fast));
+
+        for vector in &vectors {
+            assert!(DerivationScheme::from_name(&vector.scheme).is_some());
+            assert_eq!(vector.key.len(), 64);
+            assert!(!vector.layers.is_empty());
+
+            let modes: HashSet<_> = vector.outputs.iter().map(|o| o.mode.as_str()).collect();
+            assert!(
+                modes.contains("mnemonic"),
+                "{} lacks a mnemonic",
+                vector.name
+            );
+            assert!(
+                modes.contains("password"),
+                "{} lacks a password",
+                vector.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_vectors_coverage() {
+        let vectors = test_vectors().unwrap();
+        let standard = Argon2Config::STANDARD;
+        let paranoid = Argon2Config::PARANOID;
+
+        let uses = |config: Argon2Config| {
+            vectors.iter().any(|v| {
+                v.kdf.memory_kib == config.memory_kib
+                    && v.kdf.iterations == config.iterations
+                    && v.kdf.parallelism == config.parallelism
+            })
+        };
+        assert!(uses(standard), "Missing Standard vectors");
+        assert!(uses(paranoid), "Missing Paranoid vectors");
+
+        let layers = || vectors.iter().flat_map(|v| v.layers.iter());
+        assert!(layers().any(|l| l.len() < 16), "Missing short layers");
+        assert!(layers().any(|l| l.len() >= 16), "Missing long layers");
+        assert!(layers().any(|l| !l.is_ascii()), "Missing Unicode layers");
+    }
+
+    #[test]
+    fn test_vectors_verify() {
+        for vector in test_vectors().unwrap().iter().filter(|v| v.fast) {
+            verify_vector(vector)
+                .unwrap_or_else(|e| panic!("Vector \"{}\" failed: {}", vector.name, e));
+        }
+    }
+
+    // Paranoid vectors take minutes in debug builds; `selftest --full` covers them
+    #[test]
+    #[ignore]
+    fn test_vectors_verify_full() {
+        for vector in test_vectors().unwrap().iter().filter(|v| !v.fast) {
+            verify_vector(vector)
+                .unwrap_or_else(|e| panic!("Vector \"{}\" failed: {}", vector.name, e));
+        }
+    }
+
+    #[test]
+    fn test_vectors_detect_mismatch() {
+        let mut vectors = test_vectors().unwrap();
+        let mut vector = vectors.remove(vectors.iter().position(|v| v.fast).unwrap());
+
+        vector.outputs[0].value.push('x');
+        assert!(verify_vector(&vector).is_err());
+
+        vector.key = "00".repeat(32);
+        assert!(verify_vector(&vector).is_err());
+    }
+}
@@ -0,0 +1,461 @@
+// This file is part of Qatsi.
+//
+// Copyright (c) 2025  René Coignard <contact@renecoignard.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{Context, Result};
+use blake2::{Blake2b512, Digest};
+use chacha20::ChaCha20;
+use chacha20::cipher::{KeyIvInit, StreamCipher};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+const SHARE_PREFIX: &str = "qatsi-share-v1";
+const COEFFICIENT_DOMAIN: &[u8] = b"qatsi-shamir-v1-coefficients";
+const FINGERPRINT_DOMAIN: &[u8] = b"qatsi-shamir-v1-fingerprint";
+
+const KEY_LEN: usize = 32;
+const FINGERPRINT_LEN: usize = 4;
+const CHECKSUM_LEN: usize = 4;
+
+pub const MIN_THRESHOLD: usize = 2;
+pub const MAX_SHARES: usize = 255;
+
+pub struct Share {
+    pub threshold: usize,
+    pub index: u8,
+    pub fingerprint: [u8; FINGERPRINT_LEN],
+    value: Zeroizing<[u8; KEY_LEN]>,
+}
+
+// GF(2^8) with the AES polynomial x^8 + x^4 + x^3 + x + 1, computed without
+// table lookups or secret-dependent branches.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & (b & 1).wrapping_neg();
+        let carry = (a >> 7).wrapping_neg();
+        a = (a << 1) ^ (0x1b & carry);
+        b >>= 1;
+    }
+    product
+}
+
+fn gf_inv(a: u8) -> u8 {
+    // a^254 = a^-1 for a != 0
+    let mut result = 1u8;
+    let mut base = a;
+    let mut exponent = 254u8;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str, len: usize) -> Option<Vec<u8>> {
+    if s.len() != len * 2 || !s.is_ascii() {
+        return None;
+    }
+    (0..len)
+        .map(|i| u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).ok())
+        .collect()
+}
+
+pub fn key_fingerprint(key: &[u8; KEY_LEN]) -> [u8; FINGERPRINT_LEN] {
+    let mut hasher = Blake2b512::new();
+    hasher.update(FINGERPRINT_DOMAIN);
+    hasher.update(key);
+    let digest = hasher.finalize();
+
+    let mut fingerprint = [0u8; FINGERPRINT_LEN];
+    fingerprint.copy_from_slice(&digest[..FINGERPRINT_LEN]);
+    fingerprint
+}
+
+pub fn check_parameters(threshold: usize, share_count: usize) -> Result<()> {
+    if threshold < MIN_THRESHOLD {
+        anyhow::bail!("Share threshold must be at least {}", MIN_THRESHOLD);
+    }
+    if share_count < threshold {
+        anyhow::bail!(
+            "Share count ({}) must not be below the threshold ({})",
+            share_count,
+            threshold
+        );
+    }
+    if share_count > MAX_SHARES {
+        anyhow::bail!("Too many shares ({} maximum allowed)", MAX_SHARES);
+    }
+    Ok(())
+}
+
+// Coefficients depend only on the key and the threshold, so re-running with
+// a larger share count extends an existing set instead of replacing it.
+fn coefficients(key: &[u8; KEY_LEN], threshold: usize) -> Zeroizing<Vec<[u8; KEY_LEN]>> {
+    let mut hasher = Blake2b512::new();
+    hasher.update(COEFFICIENT_DOMAIN);
+    hasher.update([threshold as u8]);
+    hasher.update(key);
+    let digest = Zeroizing::new(hasher.finalize().to_vec());
+
+    let mut seed = Zeroizing::new([0u8; KEY_LEN]);
+    seed.copy_from_slice(&digest[..KEY_LEN]);
+
+    let mut cipher = ChaCha20::new((&*seed).into(), &[0u8; 12].into());
+    let mut coefficients = Zeroizing::new(vec![[0u8; KEY_LEN]; threshold]);
+    coefficients[0] = *key;
+    for coefficient in coefficients.iter_mut().skip(1) {
+        cipher.apply_keystream(coefficient);
+    }
+
+    coefficients
+}
+
+pub fn split_key(key: &[u8; KEY_LEN], threshold: usize, share_count: usize) -> Result<Vec<Share>> {
+    check_parameters(threshold, share_count)?;
+
+    let coefficients = coefficients(key, threshold);
+    let fingerprint = key_fingerprint(key);
+
+    let shares = (1..=share_count as u8)
+        .map(|x| {
+            let mut value = Zeroizing::new([0u8; KEY_LEN]);
+            for (i, byte) in value.iter_mut().enumerate() {
+                *byte = coefficients
+                    .iter()
+                    .rev()
+                    .fold(0u8, |acc, coefficient| gf_mul(acc, x) ^ coefficient[i]);
+            }
+
+            Share {
+                threshold,
+                index: x,
+                fingerprint,
+                value,
+            }
+        })
+        .collect();
+
+    Ok(shares)
+}
+
+fn share_checksum(body: &str) -> [u8; CHECKSUM_LEN] {
+    let mut hasher = Sha256::new();
+    hasher.update(body.as_bytes());
+    let digest = hasher.finalize();
+
+    let mut checksum = [0u8; CHECKSUM_LEN];
+    checksum.copy_from_slice(&digest[..CHECKSUM_LEN]);
+    checksum
+}
+
+impl Share {
+    pub fn encode(&self) -> Zeroizing<String> {
+        let value = Zeroizing::new(to_hex(&*self.value));
+        let body = Zeroizing::new(format!(
+            "{}-{}-{}-{}-{}",
+            SHARE_PREFIX,
+            self.threshold,
+            self.index,
+            to_hex(&self.fingerprint),
+            &*value
+        ));
+        let checksum = share_checksum(&body);
+
+        Zeroizing::new(format!("{}-{}", &*body, to_hex(&checksum)))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = Zeroizing::new(text.trim().to_ascii_lowercase());
+
+        let (body, checksum) = text
+            .rsplit_once('-')
+            .context("Malformed share: missing checksum")?;
+        let rest = body
+            .strip_prefix(SHARE_PREFIX)
+            .and_then(|rest| rest.strip_prefix('-'))
+            .context("Malformed share: expected qatsi-share-v1 prefix")?;
+
+        let fields: Vec<&str> = rest.split('-').collect();
+        if fields.len() != 4 {
+            anyhow::bail!("Malformed share: expected 4 fields after the prefix");
+        }
+
+        if from_hex(checksum, CHECKSUM_LEN).as_deref() != Some(&share_checksum(body)[..]) {
+            anyhow::bail!("Share checksum mismatch; check for transcription errors");
+        }
+
+        let threshold: usize = fields[0]
+            .parse()
+            .ok()
+            .filter(|t| (MIN_THRESHOLD..=MAX_SHARES).contains(t))
+            .context("Malformed share: invalid threshold")?;
+        let index: u8 = fields[1]
+            .parse()
+            .ok()
+            .filter(|&x| x != 0)
+            .context("Malformed share: invalid index")?;
+        let fingerprint: [u8; FINGERPRINT_LEN] = from_hex(fields[2], FINGERPRINT_LEN)
+            .and_then(|bytes| bytes.try_into().ok())
+            .context("Malformed share: invalid fingerprint")?;
+
+        let bytes = Zeroizing::new(
+            from_hex(fields[3], KEY_LEN).context("Malformed share: invalid share value")?,
+        );
+        let mut value = Zeroizing::new([0u8; KEY_LEN]);
+        value.copy_from_slice(&bytes);
+
+        Ok(Share {
+            threshold,
+            index,
+            fingerprint,
+            value,
+        })
+    }
+}
+
+pub fn generate_shares(
+    key: &[u8; KEY_LEN],
+    threshold: usize,
+    share_count: usize,
+) -> Result<Zeroizing<String>> {
+    let shares = split_key(key, threshold, share_count)?;
+    let encoded: Vec<Zeroizing<String>> = shares.iter().map(Share::encode).collect();
+
+    Ok(Zeroizing::new(
+        encoded
+            .iter()
+            .map(|share| share.as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    ))
+}
+
+pub fn combine_shares(shares: &[Share]) -> Result<Zeroizing<[u8; KEY_LEN]>> {
+    let first = shares.first().context("No shares provided")?;
+
+    for share in shares {
+        if share.threshold != first.threshold || share.fingerprint != first.fingerprint {
+            anyhow::bail!(
+                "Share {} belongs to a different set (threshold or fingerprint differs)",
+                share.index
+            );
+        }
+    }
+
+    for (i, share) in shares.iter().enumerate() {
+        if shares[..i].iter().any(|other| other.index == share.index) {
+            anyhow::bail!("Share {} was provided more than once", share.index);
+        }
+    }
+
+    if shares.len() < first.threshold {
+        anyhow::bail!(
+            "At least {} shares are required, got {}",
+            first.threshold,
+            shares.len()
+        );
+    }
+
+    // Interpolating through every provided share means a share that is
+    // inconsistent with the others changes the result and fails the
+    // fingerprint check below.
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    for (i, share) in shares.iter().enumerate() {
+        let mut numerator = 1u8;
+        let mut denominator = 1u8;
+        for (j, other) in shares.iter().enumerate() {
+            if i != j {
+                numerator = gf_mul(numerator, other.index);
+                denominator = gf_mul(denominator, other.index ^ share.index);
+            }
+        }
+        let basis = gf_mul(numerator, gf_inv(denominator));
+
+        for (byte, value) in key.iter_mut().zip(share.value.iter()) {
+            *byte ^= gf_mul(basis, *value);
+        }
+    }
+
+    if key_fingerprint(&key) != first.fingerprint {
+        anyhow::bail!("Recovered key does not match the share fingerprint");
+    }
+
+    Ok(key)
+}
+
+pub fn format_fingerprint(fingerprint: &[u8; FINGERPRINT_LEN]) -> String {
+    to_hex(fingerprint)
+}
+
+pub fn format_key(key: &[u8; KEY_LEN]) -> Zeroizing<String> {
+    Zeroizing::new(to_hex(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_key() -> [u8; KEY_LEN] {
+        let mut key = [0u8; KEY_LEN];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = (i as u8).wrapping_mul(37).wrapping_add(11);
+        }
+        key
+    }
+
+    fn parse_all(encoded: &str) -> Vec<Share> {
+        encoded.lines().map(|s| Share::parse(s).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_gf_arithmetic() {
+        // FIPS-197 section 4.2 example
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf_mul(0x57, 0x13), 0xfe);
+
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1, "inverse of {:#04x}", a);
+        }
+    }
+
+    #[test]
+    fn test_combine_any_threshold_subset() {
+        let key = test_key();
+        let shares = parse_all(&generate_shares(&key, 3, 5).unwrap());
+
+        for a in 0..5 {
+            for b in a + 1..5 {
+                for c in b + 1..5 {
+                    let subset: Vec<Share> = [a, b, c]
+                        .iter()
+                        .map(|&i| Share::parse(&shares[i].encode()).unwrap())
+                        .collect();
+                    assert_eq!(*combine_shares(&subset).unwrap(), key);
+                }
+            }
+        }
+
+        assert_eq!(*combine_shares(&shares).unwrap(), key);
+    }
+
+    #[test]
+    fn test_shares_are_deterministic_and_extendable() {
+        let key = test_key();
+
+        let three = generate_shares(&key, 2, 3).unwrap();
+        let five = generate_shares(&key, 2, 5).unwrap();
+
+        assert_eq!(*three, *generate_shares(&key, 2, 3).unwrap());
+        assert!(five.starts_with(three.as_str()));
+        assert_ne!(
+            *three,
+            *generate_shares(&key, 3, 3).unwrap(),
+            "Different thresholds must use independent polynomials"
+        );
+    }
+
+    #[test]
+    fn test_share_format() {
+        let key = test_key();
+        let encoded = generate_shares(&key, 2, 3).unwrap();
+        let first = encoded.lines().next().unwrap();
+
+        let fields: Vec<&str> = first.split('-').collect();
+        assert_eq!(fields.len(), 8);
+        assert_eq!(&fields[..3], &["qatsi", "share", "v1"]);
+        assert_eq!(fields[3], "2");
+        assert_eq!(fields[4], "1");
+        assert_eq!(fields[5], format_fingerprint(&key_fingerprint(&key)));
+        assert_eq!(fields[6].len(), 64);
+        assert_eq!(fields[7].len(), 8);
+
+        assert!(Share::parse(&format!("  {}\n", first.to_uppercase())).is_ok());
+    }
+
+    #[test]
+    fn test_share_known_answer() {
+        let encoded = generate_shares(&test_key(), 2, 3).unwrap();
+
+        assert_eq!(
+            encoded.lines().nth(1).unwrap(),
+            "qatsi-share-v1-2-2-a901d273-784f0de5a751f91a02e0b60f4d7cc2dcc48255f66d3e05ce283d707b70e0254d-dc781c71"
+        );
+    }
+
+    #[test]
+    fn test_below_threshold_rejected() {
+        let key = test_key();
+        let shares = parse_all(&generate_shares(&key, 3, 5).unwrap());
+
+        assert!(combine_shares(&shares[..2]).is_err());
+        assert!(combine_shares(&[]).is_err());
+    }
+
+    #[test]
+    fn test_corrupted_shares_rejected() {
+        let key = test_key();
+        let encoded = generate_shares(&key, 2, 3).unwrap();
+        let first = encoded.lines().next().unwrap();
+
+        let mut typo = first.to_string().into_bytes();
+        let pos = SHARE_PREFIX.len() + 20;
+        typo[pos] = if typo[pos] == b'0' { b'1' } else { b'0' };
+        assert!(Share::parse(std::str::from_utf8(&typo).unwrap()).is_err());
+
+        assert!(Share::parse("qatsi-share-v1-2-1").is_err());
+        assert!(Share::parse("not a share").is_err());
+
+        let mut shares = parse_all(&encoded);
+        shares[1].value[0] ^= 1;
+        assert!(combine_shares(&shares[..2]).is_err());
+
+        let duplicate = vec![Share::parse(first).unwrap(), Share::parse(first).unwrap()];
+        assert!(combine_shares(&duplicate).is_err());
+    }
+
+    #[test]
+    fn test_mixed_sets_rejected() {
+        let mut other_key = test_key();
+        other_key[0] ^= 0xff;
+
+        let mut shares = parse_all(&generate_shares(&test_key(), 2, 2).unwrap());
+        shares.truncate(1);
+        shares.extend(
+            parse_all(&generate_shares(&other_key, 2, 2).unwrap())
+                .into_iter()
+                .skip(1),
+        );
+
+        assert!(combine_shares(&shares).is_err());
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        assert!(check_parameters(1, 3).is_err());
+        assert!(check_parameters(4, 3).is_err());
+        assert!(check_parameters(2, 256).is_err());
+        assert!(check_parameters(2, 2).is_ok());
+        assert!(check_parameters(255, 255).is_ok());
+    }
+}
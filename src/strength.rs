@@ -0,0 +1,559 @@
+// This file is part of Qatsi.
+//
+// Copyright (c) 2025  René Coignard <contact@renecoignard.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::wordlist::get_wordlist;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use zeroize::Zeroizing;
+
+// Longer secrets are estimated from their prefix; by then any realistic
+// master is well past every threshold.
+pub const MAX_ESTIMATE_CHARS: usize = 256;
+
+const MIN_MATCH_LEN: usize = 3;
+const MAX_DICTIONARY_WORD_LEN: usize = 16;
+const MAX_REPEAT_UNIT: usize = 32;
+
+// Most frequent leaked passwords, most common first.
+const COMMON_PASSWORDS: &[&str] = &[
+    "123456",
+    "password",
+    "123456789",
+    "12345678",
+    "12345",
+    "qwerty",
+    "1234567",
+    "111111",
+    "123123",
+    "abc123",
+    "1234567890",
+    "password1",
+    "iloveyou",
+    "1q2w3e4r",
+    "000000",
+    "qwerty123",
+    "zaq12wsx",
+    "dragon",
+    "sunshine",
+    "princess",
+    "letmein",
+    "654321",
+    "monkey",
+    "1qaz2wsx",
+    "123321",
+    "qwertyuiop",
+    "superman",
+    "asdfghjkl",
+    "trustno1",
+    "football",
+    "baseball",
+    "welcome",
+    "master",
+    "shadow",
+    "michael",
+    "jennifer",
+    "hunter",
+    "charlie",
+    "admin",
+    "login",
+    "starwars",
+    "freedom",
+    "whatever",
+    "qazwsx",
+    "ninja",
+    "mustang",
+    "access",
+    "secret",
+    "hello",
+    "flower",
+    "loveme",
+    "batman",
+    "killer",
+    "ashley",
+    "bailey",
+];
+
+// QWERTY rows, slanted as on a real keyboard: a key's upper neighbours are
+// at the same and the next column of the row above.
+const KEYBOARD_ROWS: [&str; 4] = [
+    "`1234567890-=",
+    " qwertyuiop[]\\",
+    " asdfghjkl;'",
+    " zxcvbnm,./",
+];
+const SHIFTED_ROWS: [&str; 4] = [
+    "~!@#$%^&*()_+",
+    " QWERTYUIOP{}|",
+    " ASDFGHJKL:\"",
+    " ZXCVBNM<>?",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern {
+    Dictionary,
+    Repeat,
+    Sequence,
+    Keyboard,
+    Bruteforce,
+}
+
+impl Pattern {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pattern::Dictionary => "dictionary",
+            Pattern::Repeat => "repeat",
+            Pattern::Sequence => "sequence",
+            Pattern::Keyboard => "keyboard",
+            Pattern::Bruteforce => "bruteforce",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Estimate {
+    pub bits: f64,
+    // Weak patterns on the cheapest path, in order of appearance
+    pub patterns: Vec<Pattern>,
+}
+
+struct Match {
+    start: usize,
+    end: usize,
+    bits: f64,
+    pattern: Pattern,
+}
+
+fn dictionary() -> &'static HashMap<&'static str, f64> {
+    static DICTIONARY: OnceLock<HashMap<&'static str, f64>> = OnceLock::new();
+    DICTIONARY.get_or_init(|| {
+        let wordlist = get_wordlist();
+        let mut dictionary: HashMap<&'static str, f64> = wordlist
+            .iter()
+            .map(|&word| (word, wordlist.len() as f64))
+            .collect();
+        for (rank, &password) in COMMON_PASSWORDS.iter().enumerate() {
+            dictionary.insert(password, (rank + 1) as f64);
+        }
+        dictionary
+    })
+}
+
+fn keyboard() -> &'static HashMap<char, (i32, i32, bool)> {
+    static KEYBOARD: OnceLock<HashMap<char, (i32, i32, bool)>> = OnceLock::new();
+    KEYBOARD.get_or_init(|| {
+        let mut keys = HashMap::new();
+        for (shifted, rows) in [(false, KEYBOARD_ROWS), (true, SHIFTED_ROWS)] {
+            for (row, keys_in_row) in rows.iter().enumerate() {
+                for (col, key) in keys_in_row.chars().enumerate() {
+                    if key != ' ' {
+                        keys.insert(key, (row as i32, col as i32, shifted));
+                    }
+                }
+            }
+        }
+        keys
+    })
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+fn unleet(c: char) -> char {
+    match c {
+        '4' | '@' => 'a',
+        '8' => 'b',
+        '(' => 'c',
+        '3' => 'e',
+        '6' | '9' => 'g',
+        '1' | '!' => 'i',
+        '|' => 'l',
+        '0' => 'o',
+        '$' | '5' => 's',
+        '7' | '+' => 't',
+        '2' => 'z',
+        _ => c,
+    }
+}
+
+fn uppercase_bits(token: &str) -> f64 {
+    let upper = token.chars().filter(|c| c.is_uppercase()).count();
+    let lower = token.chars().filter(|c| c.is_lowercase()).count();
+
+    let first_only = upper == 1 && token.chars().next().is_some_and(char::is_uppercase);
+    let last_only = upper == 1 && token.chars().last().is_some_and(char::is_uppercase);
+
+    if upper == 0 {
+        0.0
+    } else if lower == 0 || first_only || last_only {
+        1.0
+    } else {
+        (1..=upper.min(lower))
+            .map(|i| binomial(upper + lower, i))
+            .sum::<f64>()
+            .log2()
+    }
+}
+
+fn dictionary_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let dictionary = dictionary();
+
+    for start in 0..chars.len() {
+        let longest = (start + MAX_DICTIONARY_WORD_LEN).min(chars.len());
+        for end in start + MIN_MATCH_LEN..=longest {
+            let mut best: Option<f64> = None;
+
+            for reversed in [false, true] {
+                let token: Zeroizing<String> = Zeroizing::new(if reversed {
+                    chars[start..end].iter().rev().collect()
+                } else {
+                    chars[start..end].iter().collect()
+                });
+                let lower = Zeroizing::new(token.to_lowercase());
+                let plain: Zeroizing<String> = Zeroizing::new(lower.chars().map(unleet).collect());
+                let substitutions = lower
+                    .chars()
+                    .zip(plain.chars())
+                    .filter(|(a, b)| a != b)
+                    .count();
+
+                for (candidate, leet_bits) in [(&lower, 0.0), (&plain, substitutions as f64)] {
+                    if let Some(guesses) = dictionary.get(candidate.as_str()) {
+                        let bits = guesses.log2()
+                            + uppercase_bits(&token)
+                            + leet_bits
+                            + if reversed { 1.0 } else { 0.0 };
+                        best = Some(best.map_or(bits, |b: f64| b.min(bits)));
+                    }
+                }
+            }
+
+            if let Some(bits) = best {
+                matches.push(Match {
+                    start,
+                    end,
+                    bits,
+                    pattern: Pattern::Dictionary,
+                });
+            }
+        }
+    }
+}
+
+fn repeat_matches(chars: &[char], matches: &mut Vec<Match>) {
+    for unit in 1..=MAX_REPEAT_UNIT.min(chars.len() / 2) {
+        for start in 0..=chars.len() - 2 * unit {
+            let base = &chars[start..start + unit];
+
+            // Only maximal runs of units that are not themselves repeats
+            let extends_previous = start >= unit && chars[start - unit..start] == *base;
+            let periodic = (1..unit)
+                .any(|p| unit.is_multiple_of(p) && base.chunks(p).all(|c| c == &base[..p]));
+            if extends_previous || periodic {
+                continue;
+            }
+
+            let mut count = 1;
+            while start + (count + 1) * unit <= chars.len()
+                && chars[start + count * unit..start + (count + 1) * unit] == *base
+            {
+                count += 1;
+            }
+
+            if count >= 2 && count * unit >= MIN_MATCH_LEN {
+                matches.push(Match {
+                    start,
+                    end: start + count * unit,
+                    bits: estimate_chars(base).bits + (count as f64).log2(),
+                    pattern: Pattern::Repeat,
+                });
+            }
+        }
+    }
+}
+
+fn sequence_class(c: char) -> Option<(u8, f64)> {
+    match c {
+        'a'..='z' => Some((0, 26.0)),
+        'A'..='Z' => Some((1, 26.0)),
+        '0'..='9' => Some((2, 10.0)),
+        _ => None,
+    }
+}
+
+fn sequence_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let step = |a: char, b: char| match (sequence_class(a), sequence_class(b)) {
+        (Some((x, _)), Some((y, _))) if x == y => Some(b as i32 - a as i32),
+        _ => None,
+    };
+
+    let mut start = 0;
+    while start + 1 < chars.len() {
+        let delta = match step(chars[start], chars[start + 1]) {
+            Some(delta) if delta.abs() == 1 => delta,
+            _ => {
+                start += 1;
+                continue;
+            }
+        };
+
+        let mut end = start + 2;
+        while end < chars.len() && step(chars[end - 1], chars[end]) == Some(delta) {
+            end += 1;
+        }
+
+        if end - start >= MIN_MATCH_LEN {
+            let base = if "aAzZ019".contains(chars[start]) {
+                4.0
+            } else {
+                sequence_class(chars[start]).map_or(26.0, |(_, size)| size)
+            };
+            matches.push(Match {
+                start,
+                end,
+                bits: base.log2()
+                    + ((end - start) as f64).log2()
+                    + if delta < 0 { 1.0 } else { 0.0 },
+                pattern: Pattern::Sequence,
+            });
+        }
+        start = end - 1;
+    }
+}
+
+fn keyboard_direction(a: char, b: char) -> Option<u8> {
+    let keys = keyboard();
+    let (r1, c1, _) = keys.get(&a)?;
+    let (r2, c2, _) = keys.get(&b)?;
+
+    match (r2 - r1, c2 - c1) {
+        (0, -1) => Some(0),
+        (0, 1) => Some(1),
+        (-1, 0) => Some(2),
+        (-1, 1) => Some(3),
+        (1, -1) => Some(4),
+        (1, 0) => Some(5),
+        _ => None,
+    }
+}
+
+// zxcvbn's spatial estimate: starting key, length and number of turns,
+// then the ways shifted keys can be placed.
+fn keyboard_bits(token: &[char], turns: usize) -> f64 {
+    let keys = keyboard();
+    let start_positions = keys.values().filter(|(_, _, shifted)| !shifted).count() as f64;
+    let degree = keys
+        .keys()
+        .filter(|&&key| !keys[&key].2)
+        .map(|&key| {
+            keys.keys()
+                .filter(|&&other| !keys[&other].2 && keyboard_direction(key, other).is_some())
+                .count() as f64
+        })
+        .sum::<f64>()
+        / start_positions;
+
+    let mut guesses = 0.0;
+    for length in 2..=token.len() {
+        for turn in 1..=turns.min(length - 1) {
+            guesses += binomial(length - 1, turn - 1) * start_positions * degree.powi(turn as i32);
+        }
+    }
+
+    let shifted = token.iter().filter(|c| keys[c].2).count();
+    let unshifted = token.len() - shifted;
+    let shift_variations = if shifted == 0 {
+        1.0
+    } else if unshifted == 0 {
+        2.0
+    } else {
+        (1..=shifted.min(unshifted))
+            .map(|i| binomial(token.len(), i))
+            .sum()
+    };
+
+    (guesses * shift_variations).log2()
+}
+
+fn keyboard_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let mut start = 0;
+    while start + 1 < chars.len() {
+        let mut end = start + 1;
+        let mut turns = 0;
+        let mut last_direction = None;
+
+        while end < chars.len() {
+            match keyboard_direction(chars[end - 1], chars[end]) {
+                Some(direction) => {
+                    if last_direction != Some(direction) {
+                        turns += 1;
+                        last_direction = Some(direction);
+                    }
+                    end += 1;
+                }
+                None => break,
+            }
+        }
+
+        if end - start >= MIN_MATCH_LEN {
+            matches.push(Match {
+                start,
+                end,
+                bits: keyboard_bits(&chars[start..end], turns),
+                pattern: Pattern::Keyboard,
+            });
+        }
+        start = if end > start + 1 { end - 1 } else { start + 1 };
+    }
+}
+
+fn bruteforce_bits(c: char) -> f64 {
+    let cardinality: f64 = if c.is_ascii_digit() {
+        10.0
+    } else if c.is_ascii_lowercase() || c.is_ascii_uppercase() {
+        26.0
+    } else if c.is_ascii() {
+        33.0
+    } else {
+        100.0
+    };
+    cardinality.log2()
+}
+
+// Cheapest split of the secret into matches and single bruteforced
+// characters, as in zxcvbn but without its factorial term for the number
+// of matches, which keeps the estimate on the low side.
+fn estimate_chars(chars: &[char]) -> Estimate {
+    let mut matches = Vec::new();
+    dictionary_matches(chars, &mut matches);
+    repeat_matches(chars, &mut matches);
+    sequence_matches(chars, &mut matches);
+    keyboard_matches(chars, &mut matches);
+
+    let mut best: Vec<(f64, usize, Pattern)> =
+        vec![(f64::INFINITY, 0, Pattern::Bruteforce); chars.len() + 1];
+    best[0].0 = 0.0;
+
+    for end in 1..=chars.len() {
+        best[end] = (
+            best[end - 1].0 + bruteforce_bits(chars[end - 1]),
+            end - 1,
+            Pattern::Bruteforce,
+        );
+        for m in matches.iter().filter(|m| m.end == end) {
+            let bits = best[m.start].0 + m.bits;
+            if bits < best[end].0 {
+                best[end] = (bits, m.start, m.pattern);
+            }
+        }
+    }
+
+    let mut patterns = Vec::new();
+    let mut position = chars.len();
+    while position > 0 {
+        let (_, start, pattern) = best[position];
+        if pattern != Pattern::Bruteforce {
+            patterns.push(pattern);
+        }
+        position = start;
+    }
+    patterns.reverse();
+    patterns.dedup();
+
+    Estimate {
+        bits: best[chars.len()].0,
+        patterns,
+    }
+}
+
+pub fn estimate(secret: &str) -> Estimate {
+    let chars: Zeroizing<Vec<char>> =
+        Zeroizing::new(secret.chars().take(MAX_ESTIMATE_CHARS).collect());
+    estimate_chars(&chars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeats_are_weak() {
+        let result = estimate("aaaaaaaaaaaaaaaa");
+        assert!(result.bits < 10.0, "{}", result.bits);
+        assert_eq!(result.patterns, [Pattern::Repeat]);
+
+        let result = estimate("abcXabcXabcXabcX");
+        assert!(result.bits < 30.0, "{}", result.bits);
+        assert!(result.patterns.contains(&Pattern::Repeat));
+    }
+
+    #[test]
+    fn test_common_passwords_are_weak() {
+        assert!(estimate("password").bits < 2.0);
+        assert!(estimate("P@ssw0rd").bits < 10.0);
+        assert!(estimate("drowssap").bits < 5.0);
+        assert_eq!(estimate("P@ssw0rd").patterns, [Pattern::Dictionary]);
+    }
+
+    #[test]
+    fn test_sequences_are_weak() {
+        let result = estimate("abcdefghijklmnop");
+        assert!(result.bits < 10.0, "{}", result.bits);
+        assert_eq!(result.patterns, [Pattern::Sequence]);
+
+        assert!(estimate("9876543210").bits < 10.0);
+    }
+
+    #[test]
+    fn test_keyboard_patterns_are_weak() {
+        for pattern in ["zxcvbnm,./", "1qaz2wsx3edc", "poiuytrewq"] {
+            let result = estimate(pattern);
+            assert!(result.bits < 30.0, "{}: {}", pattern, result.bits);
+        }
+        assert_eq!(estimate("zxcvbnm,./").patterns, [Pattern::Keyboard]);
+    }
+
+    #[test]
+    fn test_random_secrets_are_strong() {
+        let result = estimate("Xk9#mQ2$vL7!pR4&");
+        assert!(result.bits > 70.0, "{}", result.bits);
+        assert!(result.patterns.is_empty());
+    }
+
+    #[test]
+    fn test_passphrase_words() {
+        let result = estimate("correct horse battery staple");
+        assert!(result.patterns.contains(&Pattern::Dictionary));
+        assert!(result.bits > 40.0 && result.bits < 110.0, "{}", result.bits);
+    }
+
+    #[test]
+    fn test_empty_and_long_secrets() {
+        assert_eq!(estimate("").bits, 0.0);
+
+        let long = "a".repeat(1 << 20);
+        assert!(estimate(&long).bits < 20.0);
+    }
+
+    #[test]
+    fn test_uppercase_bits() {
+        assert_eq!(uppercase_bits("word"), 0.0);
+        assert_eq!(uppercase_bits("Word"), 1.0);
+        assert_eq!(uppercase_bits("WORD"), 1.0);
+        assert!(uppercase_bits("wOrD") > 1.0);
+    }
+}
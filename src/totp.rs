@@ -0,0 +1,256 @@
+// This file is part of Qatsi.
+//
+// Copyright (c) 2025  René Coignard <contact@renecoignard.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::generator::base32_encode;
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use zeroize::Zeroizing;
+
+pub const DEFAULT_SECRET_BYTES: usize = 20;
+pub const MIN_SECRET_BYTES: usize = 16;
+pub const MAX_SECRET_BYTES: usize = 32;
+
+pub const DEFAULT_DIGITS: u32 = 6;
+pub const DEFAULT_PERIOD: u64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TotpParams {
+    pub secret_bytes: usize,
+    pub digits: u32,
+    pub period: u64,
+}
+
+impl Default for TotpParams {
+    fn default() -> Self {
+        TotpParams {
+            secret_bytes: DEFAULT_SECRET_BYTES,
+            digits: DEFAULT_DIGITS,
+            period: DEFAULT_PERIOD,
+        }
+    }
+}
+
+impl TotpParams {
+    pub fn check(&self) -> Result<()> {
+        if !(MIN_SECRET_BYTES..=MAX_SECRET_BYTES).contains(&self.secret_bytes) {
+            anyhow::bail!(
+                "TOTP secret length must be between {} and {} bytes, got {}",
+                MIN_SECRET_BYTES,
+                MAX_SECRET_BYTES,
+                self.secret_bytes
+            );
+        }
+        if !(6..=8).contains(&self.digits) {
+            anyhow::bail!("TOTP digits must be 6, 7 or 8, got {}", self.digits);
+        }
+        if self.period == 0 {
+            anyhow::bail!("TOTP period must be at least 1 second");
+        }
+        Ok(())
+    }
+}
+
+pub fn totp_secret(key: &[u8; 32], params: &TotpParams) -> Result<Zeroizing<Vec<u8>>> {
+    params.check()?;
+    Ok(Zeroizing::new(key[..params.secret_bytes].to_vec()))
+}
+
+pub fn hotp(secret: &[u8], counter: u64, digits: u32) -> Result<u32> {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).context("Invalid HOTP secret")?;
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = (digest[19] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+
+    Ok(binary % 10u32.pow(digits))
+}
+
+pub fn totp(secret: &[u8], unix_time: u64, params: &TotpParams) -> Result<u32> {
+    hotp(secret, unix_time / params.period, params.digits)
+}
+
+pub fn seconds_remaining(unix_time: u64, params: &TotpParams) -> u64 {
+    params.period - unix_time % params.period
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+pub fn otpauth_uri(
+    key: &[u8; 32],
+    issuer: &str,
+    account: &str,
+    params: &TotpParams,
+) -> Result<Zeroizing<String>> {
+    if account.is_empty() {
+        anyhow::bail!("TOTP account name cannot be empty");
+    }
+
+    let secret = totp_secret(key, params)?;
+    let encoded_secret = base32_encode(&secret);
+
+    let label = if issuer.is_empty() {
+        percent_encode(account)
+    } else {
+        format!("{}:{}", percent_encode(issuer), percent_encode(account))
+    };
+
+    let mut uri = Zeroizing::new(format!(
+        "otpauth://totp/{}?secret={}",
+        label, &*encoded_secret
+    ));
+    if !issuer.is_empty() {
+        uri.push_str(&format!("&issuer={}", percent_encode(issuer)));
+    }
+    uri.push_str(&format!(
+        "&algorithm=SHA1&digits={}&period={}",
+        params.digits, params.period
+    ));
+
+    Ok(uri)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn test_hotp_rfc4226_vectors() {
+        let expected = [
+            755224, 287082, 359152, 969429, 338314, 254676, 287922, 162583, 399871, 520489,
+        ];
+
+        for (counter, &code) in expected.iter().enumerate() {
+            assert_eq!(hotp(RFC_SECRET, counter as u64, 6).unwrap(), code);
+        }
+    }
+
+    #[test]
+    fn test_totp_rfc6238_vectors() {
+        let params = TotpParams {
+            secret_bytes: RFC_SECRET.len(),
+            digits: 8,
+            period: 30,
+        };
+        let vectors = [
+            (59, 94287082),
+            (1111111109, 7081804),
+            (1111111111, 14050471),
+            (1234567890, 89005924),
+            (2000000000, 69279037),
+            (20000000000, 65353130),
+        ];
+
+        for (time, code) in vectors {
+            assert_eq!(totp(RFC_SECRET, time, &params).unwrap(), code);
+        }
+    }
+
+    #[test]
+    fn test_seconds_remaining() {
+        let params = TotpParams::default();
+        assert_eq!(seconds_remaining(0, &params), 30);
+        assert_eq!(seconds_remaining(59, &params), 1);
+        assert_eq!(seconds_remaining(60, &params), 30);
+    }
+
+    #[test]
+    fn test_otpauth_uri() {
+        let key = [0u8; 32];
+        let uri = otpauth_uri(
+            &key,
+            "Example Co",
+            "alice@example.com",
+            &TotpParams::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            *uri,
+            "otpauth://totp/Example%20Co:alice%40example.com?secret=AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA&issuer=Example%20Co&algorithm=SHA1&digits=6&period=30"
+        );
+
+        let no_issuer = otpauth_uri(&key, "", "bob", &TotpParams::default()).unwrap();
+        assert!(no_issuer.starts_with("otpauth://totp/bob?secret="));
+        assert!(!no_issuer.contains("issuer="));
+
+        assert!(otpauth_uri(&key, "Example", "", &TotpParams::default()).is_err());
+    }
+
+    #[test]
+    fn test_secret_length() {
+        let mut key = [0u8; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+
+        let params = TotpParams {
+            secret_bytes: 32,
+            ..TotpParams::default()
+        };
+        assert_eq!(*totp_secret(&key, &params).unwrap(), key.to_vec());
+        assert_eq!(
+            base32_encode(&totp_secret(&key, &params).unwrap()).len(),
+            52
+        );
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let invalid = [
+            TotpParams {
+                secret_bytes: 10,
+                ..TotpParams::default()
+            },
+            TotpParams {
+                secret_bytes: 33,
+                ..TotpParams::default()
+            },
+            TotpParams {
+                digits: 5,
+                ..TotpParams::default()
+            },
+            TotpParams {
+                period: 0,
+                ..TotpParams::default()
+            },
+        ];
+
+        for params in invalid {
+            assert!(params.check().is_err());
+        }
+        assert!(TotpParams::default().check().is_ok());
+    }
+}
@@ -19,7 +19,10 @@ use anyhow::{Context, Result};
 use console::{Style, Term};
 use indicatif::{ProgressBar, ProgressStyle};
 use rpassword::read_password;
+use serde::Serialize;
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::{Duration, Instant};
 use unicode_normalization::UnicodeNormalization;
 use zeroize::Zeroizing;
@@ -28,6 +31,7 @@ pub const MIN_SAFE_ENTROPY: f64 = 100.0;
 pub const PARANOID_ENTROPY: f64 = 300.0;
 
 pub const MIN_MASTER_BYTES: usize = 16;
+pub const MIN_MASTER_STRENGTH_BITS: f64 = 60.0;
 pub const MIN_LAYER_BYTES: usize = 4;
 pub const MIN_LAYERS_COUNT: usize = 2;
 
@@ -43,12 +47,19 @@ pub const MIN_KDF_MEMORY_MIB_PARANOID: u32 = 64;
 pub const MIN_KDF_ITERATIONS_PARANOID: u32 = 16;
 pub const MIN_KDF_PARALLELISM_PARANOID: u32 = 4;
 
-pub const MIN_SAFE_WORD_COUNT: usize = 8;
 pub const MIN_SAFE_PASSWORD_LENGTH: usize = 20;
+pub const MIN_SAFE_SYLLABLES: usize = 16;
+pub const MIN_SAFE_PIN_LENGTH: usize = 6;
+pub const MIN_SAFE_RAW_BYTES: usize = 16;
+
+pub const JSON_SCHEMA_VERSION: u32 = 1;
 
 pub struct InputInfo {
     pub master_byte_length: usize,
     pub master_char_count: usize,
+    pub master_fingerprint: Option<String>,
+    pub master_strength: qatsi::strength::Estimate,
+    pub min_master_strength: f64,
     pub layers: Vec<LayerInfo>,
 }
 
@@ -58,18 +69,130 @@ pub struct LayerInfo {
     pub char_count: usize,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputKind {
+    Mnemonic {
+        digit: bool,
+        symbol: bool,
+        wordlist: qatsi::wordlist::WordlistKind,
+    },
+    Password,
+    Pronounceable(qatsi::generator::PronounceableOptions),
+    Pin {
+        reject_weak: bool,
+    },
+    Bip39,
+    Shamir {
+        threshold: usize,
+        share_count: usize,
+    },
+    SshEd25519 {
+        encrypted: bool,
+    },
+    Age,
+    WireGuard {
+        interface: bool,
+    },
+    TotpSeed {
+        secret_bytes: usize,
+        digits: u32,
+        period: u64,
+    },
+    Raw {
+        bytes: usize,
+        encoding: Option<qatsi::generator::Encoding>,
+    },
+}
+
+impl OutputKind {
+    fn name(&self) -> &'static str {
+        match self {
+            OutputKind::Mnemonic { .. } => "mnemonic",
+            OutputKind::Password => "password",
+            OutputKind::Pronounceable(_) => "pronounceable",
+            OutputKind::Pin { .. } => "pin",
+            OutputKind::Bip39 => "bip39",
+            OutputKind::Shamir { .. } => "shamir",
+            OutputKind::SshEd25519 { .. } => "ssh-ed25519",
+            OutputKind::Age => "age",
+            OutputKind::WireGuard { .. } => "wireguard",
+            OutputKind::TotpSeed { .. } => "totp-seed",
+            OutputKind::Raw { .. } => "raw",
+        }
+    }
+
+    // Modes that read the ChaCha20 keystream of the derived key directly
+    fn keystream(&self) -> Option<&'static str> {
+        match self {
+            OutputKind::Mnemonic { .. }
+            | OutputKind::Password
+            | OutputKind::Pronounceable(_)
+            | OutputKind::Pin { .. }
+            | OutputKind::Raw { .. } => Some("chacha20"),
+            _ => None,
+        }
+    }
+
+    fn sampling(&self) -> Option<&'static str> {
+        match self {
+            OutputKind::Mnemonic { .. }
+            | OutputKind::Password
+            | OutputKind::Pronounceable(_)
+            | OutputKind::Pin { .. } => Some("rejection"),
+            _ => None,
+        }
+    }
+
+    fn is_word_based(&self) -> bool {
+        matches!(self, OutputKind::Mnemonic { .. } | OutputKind::Bip39)
+    }
+
+    fn wordlist_name(&self) -> &'static str {
+        match self {
+            OutputKind::Bip39 => "BIP39 English",
+            OutputKind::Mnemonic { wordlist, .. } => wordlist.name(),
+            _ => "EFF Large",
+        }
+    }
+
+    fn wordlist_id(&self) -> &'static str {
+        match self {
+            OutputKind::Bip39 => "bip39-english",
+            OutputKind::Mnemonic { wordlist, .. } => wordlist.id(),
+            _ => "eff-large",
+        }
+    }
+}
+
+// Public halves of generated key pairs, as returned by the generators
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublicPart {
+    SshFingerprint(String),
+    AgeRecipient(String),
+    WireGuardKey(String),
+}
+
 pub struct OutputConfig {
     pub word_count: usize,
     pub password_length: usize,
     pub wordlist_size: usize,
     pub charset_size: usize,
-    pub is_mnemonic: bool,
+    pub entropy: f64,
+    pub kind: OutputKind,
+    pub public: Option<PublicPart>,
+}
+
+pub struct ClipOptions {
+    pub clipboard: qatsi::clipboard::Clipboard,
+    pub timeout: Duration,
 }
 
 pub struct DisplayOptions {
     pub unicode_support: bool,
     pub color_support: bool,
     pub quiet: bool,
+    pub json: bool,
+    pub clip: Option<ClipOptions>,
 }
 
 pub fn detect_unicode_support() -> bool {
@@ -88,28 +211,34 @@ pub fn get_status_symbols(unicode_support: bool) -> (&'static str, &'static str)
     }
 }
 
-fn validate_control_characters(s: &str, input_name: &str) -> Result<String> {
+fn control_character_warning(s: &str, input_name: &str) -> Option<String> {
     let control_chars: Vec<(usize, char)> = s
         .chars()
         .enumerate()
         .filter(|(_, c)| c.is_control())
         .collect();
 
-    if !control_chars.is_empty() {
-        let term = Term::stderr();
+    if control_chars.is_empty() {
+        return None;
+    }
 
-        let warning_msg = format!(
-            "WARNING: {} contains {} control character(s) at position(s): {}",
-            input_name,
-            control_chars.len(),
-            control_chars
-                .iter()
-                .map(|(pos, _)| pos.to_string())
-                .collect::<Vec<_>>()
-                .join(", ")
-        );
+    Some(format!(
+        "{} contains {} control character(s) at position(s): {}",
+        input_name,
+        control_chars.len(),
+        control_chars
+            .iter()
+            .map(|(pos, _)| pos.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
 
-        term.write_line(&warning_msg)?;
+fn validate_control_characters(s: &str, input_name: &str) -> Result<String> {
+    if let Some(warning) = control_character_warning(s, input_name) {
+        let term = Term::stderr();
+
+        term.write_line(&format!("WARNING: {}", warning))?;
         term.write_str("Continue anyway? [y/N]: ")?;
         term.flush()?;
 
@@ -134,18 +263,18 @@ fn normalize_and_validate(s: &str, input_name: &str) -> Result<String> {
     validate_control_characters(&normalized, input_name)
 }
 
-pub fn prompt_master_secret() -> Result<(Zeroizing<Vec<u8>>, usize, usize)> {
-    print!("In [0]: ");
-    io::stdout().flush()?;
-
-    let password = read_password().context("Failed to fetch master secret")?;
+fn normalize_strict(s: &str, input_name: &str) -> Result<String> {
+    let trimmed = s.trim();
+    let normalized: String = trimmed.nfc().collect();
 
-    if password.is_empty() {
-        anyhow::bail!("Master secret cannot be empty");
+    if let Some(warning) = control_character_warning(&normalized, input_name) {
+        anyhow::bail!("{}", warning);
     }
 
-    let normalized = normalize_and_validate(&password, "Master secret")?;
+    Ok(normalized)
+}
 
+fn check_master_secret(normalized: String) -> Result<(Zeroizing<Vec<u8>>, usize, usize)> {
     let byte_length = normalized.len();
     if byte_length > MAX_MASTER_BYTES {
         anyhow::bail!(
@@ -163,6 +292,60 @@ pub fn prompt_master_secret() -> Result<(Zeroizing<Vec<u8>>, usize, usize)> {
     ))
 }
 
+fn check_layer(normalized: &str, index: usize) -> Result<LayerInfo> {
+    let byte_length = normalized.len();
+    if byte_length > MAX_LAYER_BYTES {
+        anyhow::bail!(
+            "Layer {} too long ({} bytes, maximum is {})",
+            index,
+            byte_length,
+            MAX_LAYER_BYTES
+        );
+    }
+
+    Ok(LayerInfo {
+        index,
+        byte_length,
+        char_count: normalized.chars().count(),
+    })
+}
+
+// Both entries are compared as NFC bytes, in constant time.
+fn confirmation_matches(normalized: &str, again: &str) -> bool {
+    let again: Zeroizing<String> = Zeroizing::new(again.trim().nfc().collect());
+    qatsi::verify::constant_time_eq(normalized.as_bytes(), again.as_bytes())
+}
+
+pub fn prompt_master_secret(confirm: bool) -> Result<(Zeroizing<Vec<u8>>, usize, usize)> {
+    eprint!("In [0]: ");
+    io::stderr().flush()?;
+
+    let password = read_password().context("Failed to fetch master secret")?;
+
+    if password.is_empty() {
+        anyhow::bail!("Master secret cannot be empty");
+    }
+
+    let normalized = normalize_and_validate(&password, "Master secret")?;
+
+    if confirm {
+        eprint!("In [0] (again): ");
+        io::stderr().flush()?;
+
+        let again = Zeroizing::new(read_password().context("Failed to fetch master secret")?);
+        if !confirmation_matches(&normalized, &again) {
+            anyhow::bail!("Master secrets do not match");
+        }
+    }
+
+    check_master_secret(normalized)
+}
+
+// On stderr, so it never precedes a JSON report on stdout
+pub fn display_master_fingerprint(fingerprint: &str) {
+    eprintln!("Fingerprint: {}", fingerprint);
+}
+
 pub fn prompt_layers() -> Result<(Vec<Zeroizing<String>>, Vec<LayerInfo>)> {
     let mut layers = Vec::new();
     let mut layer_infos = Vec::new();
@@ -173,8 +356,8 @@ pub fn prompt_layers() -> Result<(Vec<Zeroizing<String>>, Vec<LayerInfo>)> {
             anyhow::bail!("Too many layers ({} maximum allowed)", MAX_LAYERS_COUNT);
         }
 
-        print!("In [{}]: ", index);
-        io::stdout().flush()?;
+        eprint!("In [{}]: ", index);
+        io::stderr().flush()?;
 
         let mut input = String::new();
         io::stdin().read_line(&mut input)?;
@@ -186,45 +369,290 @@ pub fn prompt_layers() -> Result<(Vec<Zeroizing<String>>, Vec<LayerInfo>)> {
 
         let normalized = normalize_and_validate(trimmed, &format!("Layer {}", index))?;
 
-        let byte_length = normalized.len();
-        if byte_length > MAX_LAYER_BYTES {
+        layer_infos.push(check_layer(&normalized, index)?);
+        layers.push(Zeroizing::new(normalized));
+        index += 1;
+    }
+
+    if layers.is_empty() {
+        anyhow::bail!("At least one layer is required");
+    }
+
+    Ok((layers, layer_infos))
+}
+
+pub fn prompt_shares() -> Result<Vec<Zeroizing<String>>> {
+    let mut shares = Vec::new();
+
+    loop {
+        if shares.len() >= qatsi::shamir::MAX_SHARES {
             anyhow::bail!(
-                "Layer {} too long ({} bytes, maximum is {})",
-                index,
-                byte_length,
-                MAX_LAYER_BYTES
+                "Too many shares ({} maximum allowed)",
+                qatsi::shamir::MAX_SHARES
             );
         }
 
-        let char_count = normalized.chars().count();
-        layers.push(Zeroizing::new(normalized));
-        layer_infos.push(LayerInfo {
-            index,
-            byte_length,
-            char_count,
-        });
-        index += 1;
+        eprint!("Share [{}]: ", shares.len() + 1);
+        io::stderr().flush()?;
+
+        let input = Zeroizing::new(read_password().context("Failed to fetch share")?);
+        if input.trim().is_empty() {
+            break;
+        }
+
+        shares.push(Zeroizing::new(input.trim().to_string()));
     }
 
-    if layers.is_empty() {
+    Ok(shares)
+}
+
+pub fn read_shares_file(path: &Path) -> Result<Vec<Zeroizing<String>>> {
+    let content = Zeroizing::new(
+        std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read shares from {}", path.display()))?,
+    );
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| Zeroizing::new(line.to_string()))
+        .collect())
+}
+
+pub fn prompt_mnemonic() -> Result<Zeroizing<String>> {
+    eprint!("Mnemonic: ");
+    io::stderr().flush()?;
+
+    let input = Zeroizing::new(read_password().context("Failed to fetch mnemonic")?);
+    Ok(Zeroizing::new(normalize_strict(&input, "Mnemonic")?))
+}
+
+pub fn prompt_candidate() -> Result<Zeroizing<String>> {
+    eprint!("Candidate: ");
+    io::stderr().flush()?;
+
+    let input = Zeroizing::new(read_password().context("Failed to fetch candidate")?);
+    let candidate = Zeroizing::new(normalize_strict(&input, "Candidate")?);
+
+    if candidate.is_empty() {
+        anyhow::bail!("Candidate cannot be empty");
+    }
+
+    Ok(candidate)
+}
+
+pub fn prompt_passphrase() -> Result<Zeroizing<String>> {
+    eprint!("Passphrase: ");
+    io::stderr().flush()?;
+    let passphrase = Zeroizing::new(read_password().context("Failed to fetch passphrase")?);
+
+    if passphrase.is_empty() {
+        anyhow::bail!("Passphrase cannot be empty");
+    }
+
+    eprint!("Passphrase (again): ");
+    io::stderr().flush()?;
+    let confirmation = Zeroizing::new(read_password().context("Failed to fetch passphrase")?);
+
+    if *passphrase != *confirmation {
+        anyhow::bail!("Passphrases do not match");
+    }
+
+    Ok(passphrase)
+}
+
+fn write_new_file(path: &Path, contents: &[u8], mode: u32) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(mode);
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    file.write_all(contents)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    Ok(())
+}
+
+fn public_key_path(path: &Path) -> PathBuf {
+    let mut public_path = path.as_os_str().to_owned();
+    public_path.push(".pub");
+    PathBuf::from(public_path)
+}
+
+pub fn check_key_files_absent(path: &Path) -> Result<()> {
+    for target in [path.to_path_buf(), public_key_path(path)] {
+        if target.exists() {
+            anyhow::bail!("{} already exists; refusing to overwrite", target.display());
+        }
+    }
+    Ok(())
+}
+
+pub fn check_output_file_absent(path: &Path) -> Result<()> {
+    if path.exists() {
+        anyhow::bail!("{} already exists; refusing to overwrite", path.display());
+    }
+    Ok(())
+}
+
+pub fn write_output_file(path: &Path, contents: &[u8]) -> Result<()> {
+    check_output_file_absent(path)?;
+    write_new_file(path, contents, 0o600)
+}
+
+pub fn write_key_files(path: &Path, private_key: &str, public_key: &str) -> Result<()> {
+    check_key_files_absent(path)?;
+
+    write_new_file(path, private_key.as_bytes(), 0o600)?;
+    write_new_file(
+        &public_key_path(path),
+        format!("{}\n", public_key).as_bytes(),
+        0o644,
+    )
+}
+
+#[cfg(unix)]
+pub fn read_master_secret_fd(fd: i32) -> Result<(Zeroizing<Vec<u8>>, usize, usize)> {
+    use std::io::Read;
+    use std::os::fd::FromRawFd;
+
+    if fd < 0 {
+        anyhow::bail!("Invalid file descriptor {}", fd);
+    }
+    if fd == libc::STDOUT_FILENO || fd == libc::STDERR_FILENO {
+        anyhow::bail!(
+            "File descriptor {} is stdout or stderr, not a master secret",
+            fd
+        );
+    }
+
+    // SAFETY: F_GETFD only reads the descriptor flags and fails with EBADF
+    // for descriptors that are not open.
+    if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+        return Err(io::Error::last_os_error())
+            .with_context(|| format!("File descriptor {} is not open", fd));
+    }
+
+    // SAFETY: the descriptor was checked to be open above and is handed to us
+    // by the caller for exclusive use; it is closed once the master secret
+    // has been read.
+    let mut file = unsafe { std::fs::File::from_raw_fd(fd) };
+
+    let mut bytes = Zeroizing::new(Vec::new());
+    file.read_to_end(&mut bytes)
+        .with_context(|| format!("Failed to read master secret from fd {}", fd))?;
+
+    let password = std::str::from_utf8(&bytes).context("Master secret is not valid UTF-8")?;
+
+    let normalized = normalize_strict(password, "Master secret")?;
+
+    if normalized.is_empty() {
+        anyhow::bail!("Master secret cannot be empty");
+    }
+
+    check_master_secret(normalized)
+}
+
+#[cfg(not(unix))]
+pub fn read_master_secret_fd(fd: i32) -> Result<(Zeroizing<Vec<u8>>, usize, usize)> {
+    anyhow::bail!(
+        "Reading the master secret from fd {} is not supported on this platform",
+        fd
+    )
+}
+
+pub fn read_wordlist_file(path: &Path, sha256: &str) -> Result<qatsi::wordlist::Wordlist> {
+    let bytes = std::fs::read(path)
+        .with_context(|| format!("Failed to read wordlist from {}", path.display()))?;
+
+    qatsi::wordlist::Wordlist::from_pinned_bytes(&bytes, sha256)
+        .with_context(|| format!("Invalid wordlist {}", path.display()))
+}
+
+pub fn read_layers_file(path: &Path) -> Result<(Vec<Zeroizing<String>>, Vec<LayerInfo>)> {
+    let bytes = Zeroizing::new(
+        std::fs::read(path)
+            .with_context(|| format!("Failed to read layers from {}", path.display()))?,
+    );
+
+    let content = Zeroizing::new(
+        String::from_utf8(bytes.to_vec())
+            .with_context(|| format!("Layers file {} is not valid UTF-8", path.display()))?,
+    );
+
+    parse_layers(&content)
+}
+
+fn parse_layers(content: &str) -> Result<(Vec<Zeroizing<String>>, Vec<LayerInfo>)> {
+    let delimiter = if content.contains('\0') { '\0' } else { '\n' };
+
+    let mut entries: Vec<&str> = content.split(delimiter).collect();
+    while entries.last().is_some_and(|entry| entry.trim().is_empty()) {
+        entries.pop();
+    }
+
+    if entries.is_empty() {
         anyhow::bail!("At least one layer is required");
     }
 
+    if entries.len() > MAX_LAYERS_COUNT {
+        anyhow::bail!("Too many layers ({} maximum allowed)", MAX_LAYERS_COUNT);
+    }
+
+    let mut layers = Vec::with_capacity(entries.len());
+    let mut layer_infos = Vec::with_capacity(entries.len());
+
+    for (i, entry) in entries.iter().enumerate() {
+        let index = i + 1;
+        let normalized = normalize_strict(entry, &format!("Layer {}", index))?;
+
+        if normalized.is_empty() {
+            anyhow::bail!("Layer {} cannot be empty", index);
+        }
+
+        layer_infos.push(check_layer(&normalized, index)?);
+        layers.push(Zeroizing::new(normalized));
+    }
+
     Ok((layers, layer_infos))
 }
 
-pub fn show_progress<F, T>(unicode_support: bool, f: F) -> Result<(T, Duration)>
+pub fn show_progress<F, T>(options: &DisplayOptions, f: F) -> Result<(T, Duration)>
 where
     F: FnOnce() -> Result<T>,
 {
-    println!();
+    if !options.json {
+        println!();
+    }
+    show_spinner(options, "Deriving key...", f)
+}
+
+pub fn show_spinner<F, T>(options: &DisplayOptions, message: &str, f: F) -> Result<(T, Duration)>
+where
+    F: FnOnce() -> Result<T>,
+{
+    if options.json {
+        let start = Instant::now();
+        let result = f();
+        return result.map(|r| (r, start.elapsed()));
+    }
 
     let term = Term::stdout();
     term.hide_cursor().ok();
 
     let pb = ProgressBar::new_spinner();
 
-    if unicode_support {
+    if options.unicode_support {
         pb.set_style(
             ProgressStyle::default_spinner()
                 .template("{spinner} {msg}")
@@ -244,7 +672,7 @@ where
         );
     }
 
-    pb.set_message("Deriving key...");
+    pb.set_message(message.to_string());
     pb.enable_steady_tick(Duration::from_millis(80));
 
     let start = Instant::now();
@@ -261,55 +689,223 @@ pub fn display_output(
     output: &Zeroizing<String>,
     input_info: &InputInfo,
     config: &OutputConfig,
-    kdf_config: &crate::kdf::Argon2Config,
+    kdf_config: &qatsi::kdf::Argon2Config,
+    scheme: qatsi::kdf::DerivationScheme,
     elapsed: Duration,
     options: &DisplayOptions,
-) {
-    if options.quiet {
-        println!("Out[0]:\n{}", &**output);
+) -> Result<()> {
+    // The previous contents are read before the secret replaces them, and
+    // the signal handler is in place before the secret reaches the clipboard
+    let clipped = match &options.clip {
+        Some(clip) => {
+            let pending = Arc::new(Mutex::new(Some(PendingClear {
+                clipboard: clip.clipboard.clone(),
+                secret: Zeroizing::new(output.as_bytes().to_vec()),
+                previous: clip.clipboard.paste(),
+            })));
+            clear_clipboard_on_signal(Arc::clone(&pending))?;
+            clip.clipboard.copy_secret(output.as_bytes())?;
+            Some((clip, pending))
+        }
+        None => None,
+    };
+
+    let shown = match &clipped {
+        Some((clip, _)) => Zeroizing::new(format!(
+            "(copied to the clipboard with {}, cleared in {}s)",
+            clip.clipboard.program(),
+            clip.timeout.as_secs()
+        )),
+        None => output.clone(),
+    };
+
+    if options.json {
+        let report = json_report(output, input_info, config, kdf_config, scheme, elapsed)?;
+        println!("{}", &*report);
+    } else if options.quiet {
+        println!("Out[0]:\n{}", &*shown);
     } else {
-        println!("Out[0]:\n{}\n", &**output);
+        println!("Out[0]:\n{}\n", &*shown);
 
-        let entropy = if config.is_mnemonic {
-            config.word_count as f64 * (config.wordlist_size as f64).log2()
-        } else {
-            config.password_length as f64 * (config.charset_size as f64).log2()
-        };
+        display_settings(input_info, config, kdf_config, scheme, options);
+        display_stats(config.entropy, output, config, elapsed, options);
+    }
+
+    if let Some((clip, pending)) = clipped {
+        clear_clipboard(clip, &pending)?;
+    }
+
+    Ok(())
+}
+
+struct PendingClear {
+    clipboard: qatsi::clipboard::Clipboard,
+    secret: Zeroizing<Vec<u8>>,
+    previous: Option<Zeroizing<Vec<u8>>>,
+}
+
+impl PendingClear {
+    fn restore(&self) -> Result<&'static str> {
+        Ok(
+            match self
+                .clipboard
+                .restore(&self.secret, self.previous.as_ref())?
+            {
+                qatsi::clipboard::Restored::Previous => {
+                    "Clipboard restored to its previous contents"
+                }
+                qatsi::clipboard::Restored::Cleared => "Clipboard cleared",
+                qatsi::clipboard::Restored::Replaced => {
+                    "Clipboard changed in the meantime; left as is"
+                }
+            },
+        )
+    }
+}
+
+// Whoever takes the pending clear first restores the clipboard; the lock is
+// held while doing so, so an interrupt cannot exit halfway through.
+fn clear_clipboard_on_signal(pending: Arc<Mutex<Option<PendingClear>>>) -> Result<()> {
+    ctrlc::set_handler(move || {
+        let mut pending = pending.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(clear) = pending.take() {
+            let term = Term::stderr();
+            if term.is_term() {
+                let _ = term.clear_line();
+            }
+            match clear.restore() {
+                Ok(message) => eprintln!("{}", message),
+                Err(e) => eprintln!("Failed to clear the clipboard: {:#}", e),
+            }
+        }
+        std::process::exit(130);
+    })
+    .context("Failed to install the interrupt handler for the clipboard")
+}
+
+fn clear_clipboard(clip: &ClipOptions, pending: &Mutex<Option<PendingClear>>) -> Result<()> {
+    let term = Term::stderr();
+    let deadline = Instant::now() + clip.timeout;
+
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        if term.is_term() {
+            term.clear_line()?;
+            term.write_str(&format!(
+                "Clearing clipboard in {}s (Ctrl-C clears it now)",
+                remaining.as_secs_f64().ceil()
+            ))?;
+        }
+        std::thread::sleep(remaining.min(Duration::from_secs(1)));
+    }
+    if term.is_term() {
+        term.clear_line()?;
+    }
+
+    let mut pending = pending.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(clear) = pending.take() {
+        term.write_line(clear.restore()?)?;
+    }
+
+    Ok(())
+}
+
+fn security_rating(entropy: f64) -> &'static str {
+    if entropy >= PARANOID_ENTROPY {
+        "Paranoid"
+    } else if entropy >= MIN_SAFE_ENTROPY {
+        "Strong"
+    } else {
+        "Weak"
+    }
+}
+
+pub fn kdf_floor(paranoid: bool) -> qatsi::kdf::Argon2Config {
+    if paranoid {
+        qatsi::kdf::Argon2Config {
+            memory_kib: MIN_KDF_MEMORY_MIB_PARANOID * 1024,
+            iterations: MIN_KDF_ITERATIONS_PARANOID,
+            parallelism: MIN_KDF_PARALLELISM_PARANOID,
+        }
+    } else {
+        qatsi::kdf::Argon2Config {
+            memory_kib: MIN_KDF_MEMORY_MIB_STANDARD * 1024,
+            iterations: MIN_KDF_ITERATIONS_STANDARD,
+            parallelism: MIN_KDF_PARALLELISM_STANDARD,
+        }
+    }
+}
+
+fn master_strength_is_secure(input_info: &InputInfo) -> bool {
+    input_info.master_strength.bits >= input_info.min_master_strength
+}
+
+fn master_is_secure(input_info: &InputInfo) -> bool {
+    input_info.master_byte_length >= MIN_MASTER_BYTES && master_strength_is_secure(input_info)
+}
+
+pub fn kdf_is_secure(kdf_config: &qatsi::kdf::Argon2Config) -> bool {
+    let floor = kdf_floor(kdf_config.memory_mib() >= MIN_KDF_MEMORY_MIB_PARANOID);
+    qatsi::bench::meets_floor(kdf_config, &floor)
+}
+
+fn output_length_is_secure(config: &OutputConfig) -> bool {
+    match config.kind {
+        // The word count alone means little once the list can be swapped
+        OutputKind::Mnemonic { .. } => {
+            qatsi::generator::mnemonic_entropy(config.word_count, config.wordlist_size)
+                >= MIN_SAFE_ENTROPY
+        }
+        OutputKind::Password => config.password_length >= MIN_SAFE_PASSWORD_LENGTH,
+        OutputKind::Pronounceable(options) => options.syllables >= MIN_SAFE_SYLLABLES,
+        OutputKind::Pin { .. } => config.password_length >= MIN_SAFE_PIN_LENGTH,
+        OutputKind::Bip39 => config.word_count >= qatsi::bip39::VALID_WORD_COUNTS[0],
+        OutputKind::Shamir { threshold, .. } => threshold >= qatsi::shamir::MIN_THRESHOLD,
+        OutputKind::SshEd25519 { .. } | OutputKind::Age | OutputKind::WireGuard { .. } => true,
+        OutputKind::TotpSeed { secret_bytes, .. } => secret_bytes >= qatsi::totp::MIN_SECRET_BYTES,
+        OutputKind::Raw { bytes, .. } => bytes >= MIN_SAFE_RAW_BYTES,
+    }
+}
+
+// A keyfile written to disk is measured in bytes; everything else in the
+// characters actually printed.
+fn output_length(output: &str, config: &OutputConfig) -> usize {
+    match config.kind {
+        OutputKind::Raw {
+            bytes,
+            encoding: None,
+        } => bytes,
+        _ => output.len(),
+    }
+}
 
-        display_settings(input_info, config, kdf_config, options);
-        display_stats(entropy, output.len(), config, elapsed, options);
+fn mnemonic_extras_entropy(config: &OutputConfig) -> Option<f64> {
+    match config.kind {
+        OutputKind::Mnemonic { digit, symbol, .. } if digit || symbol => Some(
+            qatsi::generator::MnemonicFormat {
+                digit,
+                symbol,
+                ..Default::default()
+            }
+            .added_entropy(),
+        ),
+        _ => None,
     }
 }
 
 fn display_settings(
     input_info: &InputInfo,
     config: &OutputConfig,
-    kdf_config: &crate::kdf::Argon2Config,
+    kdf_config: &qatsi::kdf::Argon2Config,
+    scheme: qatsi::kdf::DerivationScheme,
     options: &DisplayOptions,
 ) {
     let (check_ok, check_warn) = get_status_symbols(options.unicode_support);
 
     let memory_mib = kdf_config.memory_mib();
 
-    let (min_memory, min_iterations, min_parallelism) = if memory_mib >= MIN_KDF_MEMORY_MIB_PARANOID
-    {
-        (
-            MIN_KDF_MEMORY_MIB_PARANOID,
-            MIN_KDF_ITERATIONS_PARANOID,
-            MIN_KDF_PARALLELISM_PARANOID,
-        )
-    } else {
-        (
-            MIN_KDF_MEMORY_MIB_STANDARD,
-            MIN_KDF_ITERATIONS_STANDARD,
-            MIN_KDF_PARALLELISM_STANDARD,
-        )
-    };
-
-    let kdf_secure = memory_mib >= min_memory
-        && kdf_config.iterations >= min_iterations
-        && kdf_config.parallelism >= min_parallelism;
-    let master_bytes_secure = input_info.master_byte_length >= MIN_MASTER_BYTES;
+    let kdf_secure = kdf_is_secure(kdf_config);
+    let master_secure = master_is_secure(input_info);
+    let strength_secure = master_strength_is_secure(input_info);
     let layers_secure = input_info.layers.len() >= MIN_LAYERS_COUNT;
 
     let kdf_style = if options.color_support {
@@ -323,7 +919,7 @@ fn display_settings(
     };
 
     let master_bytes_style = if options.color_support {
-        if master_bytes_secure {
+        if master_secure {
             Style::new().green()
         } else {
             Style::new().yellow()
@@ -343,15 +939,13 @@ fn display_settings(
     };
 
     let kdf_status = if kdf_secure { check_ok } else { check_warn };
-    let master_status = if master_bytes_secure {
-        check_ok
-    } else {
-        check_warn
-    };
+    let master_status = if master_secure { check_ok } else { check_warn };
     let layers_status = if layers_secure { check_ok } else { check_warn };
 
     println!("Settings:");
 
+    println!("  ├─ Scheme     {}", scheme.name());
+
     println!(
         "  ├─ KDF        {} Argon2id (m={} MiB, t={}, p={})",
         kdf_style.apply_to(format!("[{}]", kdf_status)),
@@ -377,6 +971,51 @@ fn display_settings(
         }
     );
 
+    let strength_style = if options.color_support {
+        if strength_secure {
+            Style::new().green()
+        } else {
+            Style::new().yellow()
+        }
+    } else {
+        Style::new()
+    };
+
+    let strength = &input_info.master_strength;
+    println!(
+        "  │  {} {} Strength: ~{:.1} bits estimated{}",
+        if input_info.master_fingerprint.is_some() {
+            "├─"
+        } else {
+            "└─"
+        },
+        strength_style.apply_to(format!(
+            "[{}]",
+            if strength_secure {
+                check_ok
+            } else {
+                check_warn
+            }
+        )),
+        strength_style.apply_to(strength.bits),
+        if strength.patterns.is_empty() {
+            String::new()
+        } else {
+            format!(
+                " ({})",
+                strength
+                    .patterns
+                    .iter()
+                    .map(|pattern| pattern.name())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+    );
+    if let Some(fingerprint) = &input_info.master_fingerprint {
+        println!("  │  └─ Fingerprint {}", fingerprint);
+    }
+
     println!(
         "  ├─ Layers     {} {} {}",
         layers_style.apply_to(format!("[{}]", layers_status)),
@@ -433,29 +1072,150 @@ fn display_settings(
         );
     }
 
-    println!("  ├─ Keystream  ChaCha20 (256-bit)");
-    println!("  ├─ Sampling   Unbiased rejection");
-
-    if config.is_mnemonic {
-        println!(
-            "  └─ Output     {} {}",
-            config.word_count,
-            if config.word_count == 1 {
-                "word"
-            } else {
-                "words"
-            }
-        );
-    } else {
-        println!(
-            "  └─ Output     {} {}",
-            config.password_length,
-            if config.password_length == 1 {
-                "char"
-            } else {
-                "chars"
+    match config.kind {
+        OutputKind::Mnemonic { .. } | OutputKind::Password => {
+            println!("  ├─ Keystream  ChaCha20 (256-bit)");
+            println!("  ├─ Sampling   Unbiased rejection");
+        }
+        OutputKind::Pronounceable(_) => {
+            println!("  ├─ Keystream  ChaCha20 (256-bit)");
+            println!("  ├─ Sampling   Unbiased rejection");
+            println!("  ├─ Pattern    Consonant-vowel syllables");
+        }
+        OutputKind::Pin { reject_weak } => {
+            println!("  ├─ Keystream  ChaCha20 (256-bit)");
+            println!("  ├─ Sampling   Unbiased rejection");
+            if reject_weak {
+                println!("  ├─ Blocklist  Repeats, sequences and dates");
             }
-        );
+        }
+        OutputKind::Bip39 => {
+            println!("  ├─ Encoding   BIP39 (SHA-256 checksum)");
+        }
+        OutputKind::Shamir {
+            threshold,
+            share_count,
+        } => {
+            println!(
+                "  ├─ Sharing    Shamir GF(256) ({} of {})",
+                threshold, share_count
+            );
+        }
+        OutputKind::SshEd25519 { encrypted } => {
+            println!(
+                "  ├─ Format     OpenSSH Ed25519 ({})",
+                if encrypted {
+                    "aes256-ctr, bcrypt-pbkdf"
+                } else {
+                    "unencrypted"
+                }
+            );
+        }
+        OutputKind::Age => {
+            println!("  ├─ Format     age X25519 (Bech32)");
+        }
+        OutputKind::WireGuard { .. } => {
+            println!("  ├─ Format     WireGuard Curve25519 (clamped, base64)");
+        }
+        OutputKind::TotpSeed { digits, period, .. } => {
+            println!(
+                "  ├─ Format     RFC 6238 TOTP (SHA1, {} digits, {}s)",
+                digits, period
+            );
+        }
+        OutputKind::Raw { encoding, .. } => {
+            println!("  ├─ Keystream  ChaCha20 (256-bit)");
+            println!(
+                "  ├─ Encoding   {}",
+                match encoding {
+                    Some(encoding) => encoding.name(),
+                    None => "binary (keyfile, mode 0600)",
+                }
+            );
+        }
+    }
+
+    match config.kind {
+        OutputKind::Shamir { share_count, .. } => {
+            println!(
+                "  └─ Output     {} {}",
+                share_count,
+                if share_count == 1 { "share" } else { "shares" }
+            );
+        }
+        OutputKind::SshEd25519 { .. } => {
+            println!("  └─ Output     1 key pair");
+        }
+        OutputKind::Age => {
+            println!("  └─ Output     1 identity");
+        }
+        OutputKind::TotpSeed { secret_bytes, .. } => {
+            println!(
+                "  └─ Output     {}-bit secret (otpauth URI)",
+                secret_bytes * 8
+            );
+        }
+        OutputKind::Pronounceable(options) => {
+            println!(
+                "  └─ Output     {} {}",
+                options.syllables,
+                if options.syllables == 1 {
+                    "syllable"
+                } else {
+                    "syllables"
+                }
+            );
+        }
+        OutputKind::Pin { .. } => {
+            println!(
+                "  └─ Output     {} {}",
+                config.password_length,
+                if config.password_length == 1 {
+                    "digit"
+                } else {
+                    "digits"
+                }
+            );
+        }
+        OutputKind::Raw { bytes, .. } => {
+            println!(
+                "  └─ Output     {} {}",
+                bytes,
+                if bytes == 1 { "byte" } else { "bytes" }
+            );
+        }
+        OutputKind::WireGuard { interface } => {
+            println!(
+                "  └─ Output     {}",
+                if interface {
+                    "[Interface] stanza"
+                } else {
+                    "1 key pair"
+                }
+            );
+        }
+        kind if kind.is_word_based() => {
+            println!(
+                "  └─ Output     {} {}",
+                config.word_count,
+                if config.word_count == 1 {
+                    "word"
+                } else {
+                    "words"
+                }
+            );
+        }
+        _ => {
+            println!(
+                "  └─ Output     {} {}",
+                config.password_length,
+                if config.password_length == 1 {
+                    "char"
+                } else {
+                    "chars"
+                }
+            );
+        }
     }
 
     println!();
@@ -463,24 +1223,21 @@ fn display_settings(
 
 fn display_stats(
     entropy: f64,
-    length: usize,
+    output: &str,
     config: &OutputConfig,
     elapsed: Duration,
     options: &DisplayOptions,
 ) {
+    let length = output_length(output, config);
+    let unit = match config.kind {
+        OutputKind::Raw { encoding: None, .. } => "byte",
+        _ => "char",
+    };
     let (check_ok, check_warn) = get_status_symbols(options.unicode_support);
 
-    let (status_icon, entropy_style, status_text) = if entropy >= PARANOID_ENTROPY {
-        (
-            check_ok,
-            if options.color_support {
-                Style::new().green()
-            } else {
-                Style::new()
-            },
-            "Paranoid",
-        )
-    } else if entropy >= MIN_SAFE_ENTROPY {
+    let status_text = security_rating(entropy);
+
+    let (status_icon, entropy_style) = if entropy >= MIN_SAFE_ENTROPY {
         (
             check_ok,
             if options.color_support {
@@ -488,7 +1245,6 @@ fn display_stats(
             } else {
                 Style::new()
             },
-            "Strong",
         )
     } else {
         (
@@ -498,15 +1254,10 @@ fn display_stats(
             } else {
                 Style::new()
             },
-            "Weak",
         )
     };
 
-    let length_secure = if config.is_mnemonic {
-        config.word_count >= MIN_SAFE_WORD_COUNT
-    } else {
-        config.password_length >= MIN_SAFE_PASSWORD_LENGTH
-    };
+    let length_secure = output_length_is_secure(config);
 
     let length_style = if options.color_support {
         if length_secure {
@@ -535,10 +1286,10 @@ fn display_stats(
         length_style.apply_to(format!("[{}]", length_status))
     );
     print!("{}", length_style.apply_to(length));
-    print!(" {}", if length == 1 { "char" } else { "chars" });
+    print!(" {}{}", unit, if length == 1 { "" } else { "s" });
     println!();
 
-    if config.is_mnemonic {
+    if config.kind.is_word_based() {
         print!(
             "  ├─ Words      {} ",
             length_style.apply_to(format!("[{}]", length_status))
@@ -553,7 +1304,60 @@ fn display_stats(
             }
         );
         println!();
-        println!("  ├─ Wordlist   EFF Large ({} words)", config.wordlist_size);
+        println!(
+            "  ├─ Wordlist   {} ({} words)",
+            config.kind.wordlist_name(),
+            config.wordlist_size
+        );
+        if let (Some(extras), OutputKind::Mnemonic { digit, symbol, .. }) =
+            (mnemonic_extras_entropy(config), config.kind)
+        {
+            let added: Vec<&str> = [(digit, "digit"), (symbol, "symbol")]
+                .into_iter()
+                .filter_map(|(enabled, name)| enabled.then_some(name))
+                .collect();
+            println!("  ├─ Extras     +{:.2} bits ({})", extras, added.join(", "));
+        }
+    } else if let OutputKind::Shamir {
+        threshold,
+        share_count,
+    } = config.kind
+    {
+        println!("  ├─ Threshold  {} of {} shares", threshold, share_count);
+    } else if let Some(PublicPart::SshFingerprint(fingerprint)) = &config.public {
+        println!("  ├─ Key        {}", fingerprint);
+    } else if let Some(PublicPart::AgeRecipient(recipient)) = &config.public {
+        println!("  ├─ Recipient  {}", recipient);
+    } else if let OutputKind::TotpSeed { .. } = config.kind {
+        println!("  ├─ Encoding   Base32 (RFC 4648)");
+    } else if let Some(PublicPart::WireGuardKey(public_key)) = &config.public {
+        println!("  ├─ Public key {}", public_key);
+    } else if let OutputKind::Pronounceable(options) = config.kind {
+        println!(
+            "  ├─ Syllables  {} × {:.2} bits",
+            options.syllables,
+            options.syllable_entropy()
+        );
+        if options.capitalize {
+            println!("  ├─ Capital    {:.2} bits", options.capital_entropy());
+        }
+        if options.digits > 0 {
+            println!(
+                "  ├─ Digits     {} × {:.2} bits",
+                options.digits,
+                options.digit_entropy()
+            );
+        }
+    } else if let OutputKind::Pin { reject_weak } = config.kind {
+        println!("  ├─ Charset    {} digits", config.charset_size);
+        if reject_weak {
+            println!(
+                "  ├─ Rejected   {} weak PINs",
+                qatsi::generator::weak_pin_count(config.password_length)
+            );
+        }
+    } else if let OutputKind::Raw { .. } = config.kind {
+        println!("  ├─ Source     256-bit key, ChaCha20-expanded");
     } else {
         println!("  ├─ Charset    {} chars", config.charset_size);
     }
@@ -567,6 +1371,539 @@ fn display_stats(
     );
 }
 
+pub fn display_selftest_result(
+    name: &str,
+    result: &Result<()>,
+    elapsed: Duration,
+    options: &DisplayOptions,
+) {
+    let (check_ok, check_warn) = get_status_symbols(options.unicode_support);
+
+    let style = if options.color_support {
+        if result.is_ok() {
+            Style::new().green()
+        } else {
+            Style::new().yellow()
+        }
+    } else {
+        Style::new()
+    };
+
+    match result {
+        Ok(()) => println!(
+            "{} {} ({:.1}s)",
+            style.apply_to(format!("[{}]", check_ok)),
+            name,
+            elapsed.as_secs_f64()
+        ),
+        Err(e) => println!(
+            "{} {}: {}",
+            style.apply_to(format!("[{}]", check_warn)),
+            name,
+            style.apply_to(e)
+        ),
+    }
+}
+
+pub fn display_selftest_summary(total: usize, failures: usize, options: &DisplayOptions) {
+    let (check_ok, check_warn) = get_status_symbols(options.unicode_support);
+
+    let (status_icon, style) = if failures == 0 {
+        (
+            check_ok,
+            if options.color_support {
+                Style::new().green()
+            } else {
+                Style::new()
+            },
+        )
+    } else {
+        (
+            check_warn,
+            if options.color_support {
+                Style::new().yellow()
+            } else {
+                Style::new()
+            },
+        )
+    };
+
+    println!(
+        "\n{} Self-test: {}",
+        style.apply_to(format!("[{}]", status_icon)),
+        style.apply_to(format!("{} of {} vectors passed", total - failures, total))
+    );
+}
+
+fn status_style(ok: bool, options: &DisplayOptions) -> Style {
+    match (options.color_support, ok) {
+        (false, _) => Style::new(),
+        (true, true) => Style::new().green(),
+        (true, false) => Style::new().yellow(),
+    }
+}
+
+pub fn display_mnemonic_check(
+    checks: &[qatsi::generator::WordCheck],
+    wordlist_name: &str,
+    options: &DisplayOptions,
+) {
+    let (check_ok, check_warn) = get_status_symbols(options.unicode_support);
+    let warn_style = status_style(false, options);
+
+    for (i, check) in checks.iter().enumerate().filter(|(_, check)| !check.valid) {
+        let hint = if check.suggestions.is_empty() {
+            "no close matches".to_string()
+        } else {
+            format!("did you mean {}?", check.suggestions.join(", "))
+        };
+        println!(
+            "{} Word {} \"{}\" is not in {}; {}",
+            warn_style.apply_to(format!("[{}]", check_warn)),
+            i + 1,
+            &*check.word,
+            wordlist_name,
+            hint
+        );
+    }
+
+    let valid = checks.iter().filter(|check| check.valid).count();
+    let all_valid = valid == checks.len();
+    let style = status_style(all_valid, options);
+    println!(
+        "{} Wordlist: {}",
+        style.apply_to(format!(
+            "[{}]",
+            if all_valid { check_ok } else { check_warn }
+        )),
+        style.apply_to(format!(
+            "{} of {} words in {}",
+            valid,
+            checks.len(),
+            wordlist_name
+        ))
+    );
+}
+
+pub fn display_mnemonic_match(mismatches: &[usize], options: &DisplayOptions) {
+    let (check_ok, check_warn) = get_status_symbols(options.unicode_support);
+    let style = status_style(mismatches.is_empty(), options);
+
+    let (icon, text) = if mismatches.is_empty() {
+        (
+            check_ok,
+            "phrase matches the master secret and layers".to_string(),
+        )
+    } else {
+        (
+            check_warn,
+            format!(
+                "mismatch at word position(s) {}",
+                mismatches
+                    .iter()
+                    .map(|position| position.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        )
+    };
+
+    println!(
+        "{} Derivation: {}",
+        style.apply_to(format!("[{}]", icon)),
+        style.apply_to(text)
+    );
+}
+
+pub fn display_verify_result(matched: bool, options: &DisplayOptions) {
+    let (check_ok, check_warn) = get_status_symbols(options.unicode_support);
+    let style = status_style(matched, options);
+
+    let (icon, text) = if matched {
+        (check_ok, "candidate matches the derived secret")
+    } else {
+        (check_warn, "candidate does not match the derived secret")
+    };
+
+    println!(
+        "{} Verify: {}",
+        style.apply_to(format!("[{}]", icon)),
+        style.apply_to(text)
+    );
+}
+
+pub fn display_totp_code(code: u32, digits: u32, remaining: u64, options: &DisplayOptions) {
+    let code = Zeroizing::new(format!("{:0width$}", code, width = digits as usize));
+
+    if options.quiet {
+        println!("Out[0]:\n{}", &*code);
+        return;
+    }
+
+    println!("Out[0]:\n{}\n", &*code);
+    println!("Valid for {}s", remaining);
+}
+
+pub fn display_combined_key(
+    key: &Zeroizing<String>,
+    threshold: usize,
+    share_count: usize,
+    fingerprint: &str,
+    options: &DisplayOptions,
+) {
+    let (check_ok, _) = get_status_symbols(options.unicode_support);
+
+    let style = if options.color_support {
+        Style::new().green()
+    } else {
+        Style::new()
+    };
+
+    if options.quiet {
+        println!("Out[0]:\n{}", &**key);
+        return;
+    }
+
+    println!("Out[0]:\n{}\n", &**key);
+
+    println!("Recovery:");
+    println!(
+        "  ├─ Shares     {} provided, {} required",
+        share_count, threshold
+    );
+    println!(
+        "  └─ Verified   {} fingerprint {}",
+        style.apply_to(format!("[{}]", check_ok)),
+        style.apply_to(fingerprint)
+    );
+}
+
+fn format_kdf(config: &qatsi::kdf::Argon2Config) -> String {
+    format!(
+        "m={} MiB, t={}, p={}",
+        config.memory_mib(),
+        config.iterations,
+        config.parallelism
+    )
+}
+
+pub fn display_bench_header(
+    floor: &qatsi::kdf::Argon2Config,
+    configs: usize,
+    layers: usize,
+    target: Duration,
+) {
+    println!("Benchmark:");
+    println!(
+        "  ├─ Target     {} ms ({} {})",
+        target.as_millis(),
+        layers,
+        if layers == 1 { "layer" } else { "layers" }
+    );
+    println!("  ├─ Floor      Argon2id ({})", format_kdf(floor));
+    println!(
+        "  └─ Grid       {} {}",
+        configs,
+        if configs == 1 {
+            "configuration"
+        } else {
+            "configurations"
+        }
+    );
+    println!();
+}
+
+pub fn display_bench_sample(
+    sample: &qatsi::bench::BenchSample,
+    layers: usize,
+    target: Duration,
+    options: &DisplayOptions,
+) {
+    let (check_ok, check_warn) = get_status_symbols(options.unicode_support);
+    let within_target = sample.total(layers) <= target;
+
+    let style = if options.color_support {
+        if within_target {
+            Style::new().green()
+        } else {
+            Style::new().yellow()
+        }
+    } else {
+        Style::new()
+    };
+
+    println!(
+        "{} {}: {} ms/layer, {} ms total",
+        style.apply_to(format!(
+            "[{}]",
+            if within_target { check_ok } else { check_warn }
+        )),
+        format_kdf(&sample.config),
+        style.apply_to(sample.per_layer.as_millis()),
+        style.apply_to(sample.total(layers).as_millis())
+    );
+}
+
+pub fn display_bench_skipped(config: &qatsi::kdf::Argon2Config) {
+    println!(
+        "[-] {}: skipped (cheaper setting too slow)",
+        format_kdf(config)
+    );
+}
+
+pub fn display_bench_recommendation(
+    recommended: Option<&qatsi::bench::BenchSample>,
+    floor: &qatsi::kdf::Argon2Config,
+    layers: usize,
+    options: &DisplayOptions,
+) {
+    let (check_ok, check_warn) = get_status_symbols(options.unicode_support);
+
+    println!("\nRecommended:");
+
+    let config = match recommended {
+        Some(sample) => {
+            let style = if options.color_support {
+                Style::new().green()
+            } else {
+                Style::new()
+            };
+
+            println!(
+                "  ├─ KDF        {} Argon2id ({})",
+                style.apply_to(format!("[{}]", check_ok)),
+                style.apply_to(format_kdf(&sample.config))
+            );
+            println!("  ├─ Per layer  {} ms", sample.per_layer.as_millis());
+            println!(
+                "  └─ Total      {} ms ({} {})",
+                sample.total(layers).as_millis(),
+                layers,
+                if layers == 1 { "layer" } else { "layers" }
+            );
+
+            sample.config
+        }
+        None => {
+            let style = if options.color_support {
+                Style::new().yellow()
+            } else {
+                Style::new()
+            };
+
+            println!(
+                "  └─ KDF        {} Argon2id ({}), no configuration met the target",
+                style.apply_to(format!("[{}]", check_warn)),
+                style.apply_to(format_kdf(floor))
+            );
+
+            *floor
+        }
+    };
+
+    println!(
+        "\nqatsi --kdf-memory {} --kdf-iterations {} --kdf-parallelism {}",
+        config.memory_mib(),
+        config.iterations,
+        config.parallelism
+    );
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    schema_version: u32,
+    output: &'a str,
+    mode: &'static str,
+    settings: JsonSettings,
+    stats: JsonStats,
+}
+
+#[derive(Serialize)]
+struct JsonSettings {
+    scheme: &'static str,
+    kdf: JsonKdf,
+    master: JsonInput,
+    layers: Vec<JsonLayer>,
+    layers_secure: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keystream: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sampling: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+struct JsonKdf {
+    algorithm: &'static str,
+    memory_mib: u32,
+    iterations: u32,
+    parallelism: u32,
+    secure: bool,
+}
+
+#[derive(Serialize)]
+struct JsonInput {
+    bytes: usize,
+    chars: usize,
+    strength_bits: f64,
+    secure: bool,
+}
+
+#[derive(Serialize)]
+struct JsonLayer {
+    index: usize,
+    bytes: usize,
+    chars: usize,
+    secure: bool,
+}
+
+#[derive(Serialize)]
+struct JsonStats {
+    entropy_bits: f64,
+    rating: String,
+    secure: bool,
+    length: usize,
+    length_secure: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    words: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    wordlist: Option<JsonWordlist>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extras_entropy_bits: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    charset_size: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    syllables: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    weak_pins_rejected: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    threshold: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shares: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fingerprint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recipient: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    public_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bytes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding: Option<&'static str>,
+    elapsed_secs: f64,
+}
+
+#[derive(Serialize)]
+struct JsonWordlist {
+    name: &'static str,
+    size: usize,
+}
+
+fn json_report(
+    output: &Zeroizing<String>,
+    input_info: &InputInfo,
+    config: &OutputConfig,
+    kdf_config: &qatsi::kdf::Argon2Config,
+    scheme: qatsi::kdf::DerivationScheme,
+    elapsed: Duration,
+) -> Result<Zeroizing<String>> {
+    let entropy = config.entropy;
+
+    let report = JsonReport {
+        schema_version: JSON_SCHEMA_VERSION,
+        output,
+        mode: config.kind.name(),
+        settings: JsonSettings {
+            scheme: scheme.name(),
+            kdf: JsonKdf {
+                algorithm: "argon2id",
+                memory_mib: kdf_config.memory_mib(),
+                iterations: kdf_config.iterations,
+                parallelism: kdf_config.parallelism,
+                secure: kdf_is_secure(kdf_config),
+            },
+            master: JsonInput {
+                bytes: input_info.master_byte_length,
+                chars: input_info.master_char_count,
+                strength_bits: input_info.master_strength.bits,
+                secure: master_is_secure(input_info),
+            },
+            layers: input_info
+                .layers
+                .iter()
+                .map(|layer| JsonLayer {
+                    index: layer.index,
+                    bytes: layer.byte_length,
+                    chars: layer.char_count,
+                    secure: layer.byte_length >= MIN_LAYER_BYTES,
+                })
+                .collect(),
+            layers_secure: input_info.layers.len() >= MIN_LAYERS_COUNT,
+            keystream: config.kind.keystream(),
+            sampling: config.kind.sampling(),
+        },
+        stats: JsonStats {
+            entropy_bits: entropy,
+            rating: security_rating(entropy).to_lowercase(),
+            secure: entropy >= MIN_SAFE_ENTROPY,
+            length: output_length(output, config),
+            length_secure: output_length_is_secure(config),
+            words: config.kind.is_word_based().then_some(config.word_count),
+            wordlist: config.kind.is_word_based().then_some(JsonWordlist {
+                name: config.kind.wordlist_id(),
+                size: config.wordlist_size,
+            }),
+            extras_entropy_bits: mnemonic_extras_entropy(config),
+            charset_size: matches!(config.kind, OutputKind::Password | OutputKind::Pin { .. })
+                .then_some(config.charset_size),
+            syllables: match config.kind {
+                OutputKind::Pronounceable(options) => Some(options.syllables),
+                _ => None,
+            },
+            weak_pins_rejected: match config.kind {
+                OutputKind::Pin { reject_weak: true } => {
+                    Some(qatsi::generator::weak_pin_count(config.password_length))
+                }
+                _ => None,
+            },
+            threshold: match config.kind {
+                OutputKind::Shamir { threshold, .. } => Some(threshold),
+                _ => None,
+            },
+            shares: match config.kind {
+                OutputKind::Shamir { share_count, .. } => Some(share_count),
+                _ => None,
+            },
+            fingerprint: match &config.public {
+                Some(PublicPart::SshFingerprint(fingerprint)) => Some(fingerprint.clone()),
+                _ => None,
+            },
+            recipient: match &config.public {
+                Some(PublicPart::AgeRecipient(recipient)) => Some(recipient.clone()),
+                _ => None,
+            },
+            public_key: match &config.public {
+                Some(PublicPart::WireGuardKey(public_key)) => Some(public_key.clone()),
+                _ => None,
+            },
+            bytes: match config.kind {
+                OutputKind::Raw { bytes, .. } => Some(bytes),
+                _ => None,
+            },
+            encoding: match config.kind {
+                OutputKind::Raw { encoding, .. } => {
+                    Some(encoding.map_or("binary", |encoding| encoding.name()))
+                }
+                _ => None,
+            },
+            elapsed_secs: elapsed.as_secs_f64(),
+        },
+    };
+
+    let json = serde_json::to_string_pretty(&report).context("Failed to serialize output")?;
+
+    Ok(Zeroizing::new(json))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -679,4 +2016,186 @@ mod tests {
             assert_eq!(normalized, "");
         }
     }
+
+    #[test]
+    fn test_normalize_strict_rejects_control_characters() {
+        let result = normalize_strict("pass\u{0007}word", "Layer 1");
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Layer 1 contains 1 control character(s) at position(s): 4")
+        );
+
+        assert_eq!(normalize_strict("  cafe\u{0301} ", "test").unwrap(), "café");
+    }
+
+    #[test]
+    fn test_parse_layers_newline_delimited() {
+        let (layers, infos) = parse_layers("out\r\n  of\nbalance\n\n").unwrap();
+
+        assert_eq!(layers.len(), 3);
+        assert_eq!(&*layers[0], "out");
+        assert_eq!(&*layers[1], "of");
+        assert_eq!(&*layers[2], "balance");
+        assert_eq!(infos[2].index, 3);
+        assert_eq!(infos[2].byte_length, 7);
+    }
+
+    #[test]
+    fn test_parse_layers_nul_delimited() {
+        let (layers, infos) = parse_layers("line one\0cafe\u{0301}\0").unwrap();
+
+        assert_eq!(layers.len(), 2);
+        assert_eq!(&*layers[0], "line one");
+        assert_eq!(&*layers[1], "café");
+        assert_eq!(infos[1].byte_length, 5);
+        assert_eq!(infos[1].char_count, 4);
+    }
+
+    #[test]
+    fn test_parse_layers_rejects_invalid_input() {
+        assert!(parse_layers("").is_err());
+        assert!(parse_layers("\n\n").is_err());
+        assert!(parse_layers("out\n\nbalance").is_err());
+        assert!(parse_layers("out\nof\u{001B}\n").is_err());
+
+        let too_many = "layer\n".repeat(MAX_LAYERS_COUNT + 1);
+        assert!(parse_layers(&too_many).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_master_fd_rejects_unusable_descriptors() {
+        assert!(read_master_secret_fd(-1).is_err());
+        assert!(read_master_secret_fd(1).is_err());
+        assert!(read_master_secret_fd(2).is_err());
+        assert!(read_master_secret_fd(4095).is_err());
+    }
+
+    #[test]
+    fn test_confirmation_matches() {
+        let normalized: String = "caf\u{00E9} secret".nfc().collect();
+
+        assert!(confirmation_matches(&normalized, "caf\u{00E9} secret"));
+        assert!(confirmation_matches(&normalized, "cafe\u{0301} secret "));
+        assert!(!confirmation_matches(&normalized, "cafe secret"));
+        assert!(!confirmation_matches(&normalized, "caf\u{00E9} secre"));
+    }
+
+    #[test]
+    fn test_master_strength_check() {
+        let info = |master: &str| InputInfo {
+            master_byte_length: master.len(),
+            master_char_count: master.chars().count(),
+            master_fingerprint: None,
+            master_strength: qatsi::strength::estimate(master),
+            min_master_strength: MIN_MASTER_STRENGTH_BITS,
+            layers: Vec::new(),
+        };
+
+        assert!(!master_is_secure(&info("aaaaaaaaaaaaaaaa")));
+        assert!(!master_is_secure(&info("Password12345678")));
+        assert!(!master_is_secure(&info("Xk9#mQ2$")));
+        assert!(master_is_secure(&info("Xk9#mQ2$vL7!pR4&")));
+    }
+
+    #[test]
+    fn test_json_report_schema() {
+        let output = Zeroizing::new("6n=rX.k:Qs+)6e5oa-Z:".to_string());
+        let input_info = InputInfo {
+            master_byte_length: 16,
+            master_char_count: 16,
+            master_fingerprint: Some("unified-joystick-playmaker".to_string()),
+            master_strength: qatsi::strength::estimate("Xk9#mQ2$vL7!pR4&"),
+            min_master_strength: MIN_MASTER_STRENGTH_BITS,
+            layers: vec![
+                LayerInfo {
+                    index: 1,
+                    byte_length: 3,
+                    char_count: 3,
+                },
+                LayerInfo {
+                    index: 2,
+                    byte_length: 8,
+                    char_count: 8,
+                },
+            ],
+        };
+        let config = OutputConfig {
+            word_count: 0,
+            password_length: 20,
+            wordlist_size: 0,
+            charset_size: 90,
+            entropy: 20.0 * 90f64.log2(),
+            kind: OutputKind::Password,
+            public: None,
+        };
+
+        let report = json_report(
+            &output,
+            &input_info,
+            &config,
+            &qatsi::kdf::Argon2Config::STANDARD,
+            qatsi::kdf::DerivationScheme::V1,
+            Duration::from_millis(1500),
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&report).unwrap();
+
+        assert_eq!(value["schema_version"], JSON_SCHEMA_VERSION);
+        assert_eq!(value["output"], "6n=rX.k:Qs+)6e5oa-Z:");
+        assert_eq!(value["mode"], "password");
+        assert_eq!(value["settings"]["scheme"], "v1");
+        assert_eq!(value["settings"]["kdf"]["memory_mib"], 64);
+        assert_eq!(value["settings"]["kdf"]["secure"], true);
+        assert_eq!(value["settings"]["master"]["secure"], true);
+        assert!(value["settings"]["master"].get("fingerprint").is_none());
+        assert_eq!(value["settings"]["layers"][0]["secure"], false);
+        assert_eq!(value["settings"]["layers"][1]["index"], 2);
+        assert_eq!(value["stats"]["rating"], "strong");
+        assert_eq!(value["stats"]["charset_size"], 90);
+        assert_eq!(value["stats"]["elapsed_secs"], 1.5);
+        assert!(value["stats"].get("wordlist").is_none());
+        assert_eq!(value["settings"]["keystream"], "chacha20");
+        assert_eq!(value["settings"]["sampling"], "rejection");
+
+        let config = OutputConfig {
+            kind: OutputKind::Bip39,
+            ..config
+        };
+        let report = json_report(
+            &output,
+            &input_info,
+            &config,
+            &qatsi::kdf::Argon2Config::STANDARD,
+            qatsi::kdf::DerivationScheme::V1,
+            Duration::from_millis(1500),
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&report).unwrap();
+        assert!(value["settings"].get("keystream").is_none());
+        assert!(value["settings"].get("sampling").is_none());
+    }
+
+    #[test]
+    fn test_kdf_floor() {
+        let standard = kdf_floor(false);
+        let paranoid = kdf_floor(true);
+
+        assert_eq!(standard.memory_mib(), MIN_KDF_MEMORY_MIB_STANDARD);
+        assert_eq!(paranoid.iterations, MIN_KDF_ITERATIONS_PARANOID);
+
+        assert!(kdf_is_secure(&standard));
+        assert!(kdf_is_secure(&paranoid));
+        assert!(kdf_is_secure(&qatsi::kdf::Argon2Config::STANDARD));
+        assert!(kdf_is_secure(&qatsi::kdf::Argon2Config::PARANOID));
+
+        let below = qatsi::kdf::Argon2Config {
+            iterations: standard.iterations - 1,
+            ..standard
+        };
+        assert!(!kdf_is_secure(&below));
+    }
 }
@@ -0,0 +1,224 @@
+// This file is part of Qatsi.
+//
+// Copyright (c) 2025  René Coignard <contact@renecoignard.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::Result;
+use base64ct::{Base64, Encoding};
+use std::fmt::Write;
+use std::net::IpAddr;
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+pub struct WireGuardKeyPair {
+    pub private_key: Zeroizing<String>,
+    pub public_key: String,
+}
+
+pub struct WireGuardOutput {
+    pub contents: Zeroizing<String>,
+    pub public_key: String,
+}
+
+#[derive(Default)]
+pub struct InterfaceOptions {
+    pub address: Option<String>,
+    pub listen_port: Option<u16>,
+}
+
+// Comma-separated addresses with an optional prefix length, as accepted by
+// wg-quick, e.g. "10.0.0.2/32, fd00::2/128".
+pub fn check_address(address: &str) -> Result<()> {
+    for entry in address.split(',') {
+        let entry = entry.trim();
+        let (ip, prefix) = match entry.split_once('/') {
+            Some((ip, prefix)) => (ip, Some(prefix)),
+            None => (entry, None),
+        };
+
+        let Ok(ip) = ip.parse::<IpAddr>() else {
+            anyhow::bail!("Invalid interface address {:?}", entry);
+        };
+        if let Some(prefix) = prefix {
+            let max = if ip.is_ipv4() { 32 } else { 128 };
+            if !prefix.parse::<u8>().is_ok_and(|prefix| prefix <= max) {
+                anyhow::bail!(
+                    "Invalid prefix length in {:?}, expected 0 to {}",
+                    entry,
+                    max
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+// Same clamping as `wg genkey`, so the stored private key is already a
+// canonical Curve25519 scalar.
+pub fn clamp(key: &[u8; 32]) -> Zeroizing<[u8; 32]> {
+    let mut clamped = Zeroizing::new(*key);
+    clamped[0] &= 248;
+    clamped[31] &= 127;
+    clamped[31] |= 64;
+    clamped
+}
+
+pub fn generate_wireguard_keypair(key: &[u8; 32]) -> WireGuardKeyPair {
+    let clamped = clamp(key);
+    let secret = StaticSecret::from(*clamped);
+    let public = PublicKey::from(&secret);
+
+    WireGuardKeyPair {
+        private_key: Zeroizing::new(Base64::encode_string(&*clamped)),
+        public_key: Base64::encode_string(public.as_bytes()),
+    }
+}
+
+pub fn generate_wireguard(
+    key: &[u8; 32],
+    interface: Option<&InterfaceOptions>,
+) -> Result<WireGuardOutput> {
+    let pair = generate_wireguard_keypair(key);
+    let mut output = Zeroizing::new(String::new());
+
+    match interface {
+        None => {
+            writeln!(output, "PrivateKey = {}", &*pair.private_key)?;
+            write!(output, "PublicKey = {}", pair.public_key)?;
+        }
+        Some(options) => {
+            writeln!(output, "[Interface]")?;
+            writeln!(output, "# PublicKey = {}", pair.public_key)?;
+            write!(output, "PrivateKey = {}", &*pair.private_key)?;
+
+            if let Some(address) = &options.address {
+                check_address(address)?;
+                write!(output, "\nAddress = {}", address)?;
+            }
+            if let Some(port) = options.listen_port {
+                write!(output, "\nListenPort = {}", port)?;
+            }
+        }
+    }
+
+    Ok(WireGuardOutput {
+        contents: output,
+        public_key: pair.public_key,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_hex(s: &str) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).unwrap();
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_clamp() {
+        let clamped = clamp(&[0xff; 32]);
+        assert_eq!(clamped[0], 0xf8);
+        assert_eq!(clamped[31], 0x7f);
+        assert!(clamped[1..31].iter().all(|&b| b == 0xff));
+
+        let clamped = clamp(&[0x00; 32]);
+        assert_eq!(clamped[31], 0x40);
+    }
+
+    #[test]
+    fn test_rfc7748_key_pair() {
+        // RFC 7748 section 6.1; X25519 clamps internally, so the public key
+        // matches the published value while the private key is stored clamped
+        let alice = from_hex("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let pair = generate_wireguard_keypair(&alice);
+
+        assert_eq!(
+            pair.public_key,
+            "hSDwCYkwp1R0i33ctD73Wg2/Og0mOBr066SpjqqbTmo="
+        );
+        assert_eq!(
+            *pair.private_key,
+            "cAdtCnMYpX08FsFyUbJmRd9ML4frwJkqsXf7pR25LGo="
+        );
+    }
+
+    #[test]
+    fn test_key_format() {
+        let output = generate_wireguard(&[42u8; 32], None).unwrap();
+        let lines: Vec<&str> = output.contents.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("PrivateKey = "));
+        assert!(lines[1].starts_with("PublicKey = "));
+        assert_eq!(lines[0].len(), "PrivateKey = ".len() + 44);
+        assert!(lines[0].ends_with('='));
+        assert_eq!(lines[1], format!("PublicKey = {}", output.public_key));
+    }
+
+    #[test]
+    fn test_check_address() {
+        for valid in [
+            "10.0.0.2/32",
+            "10.0.0.2",
+            "fd00::2/128",
+            "10.0.0.2/24, fd00::2/64",
+        ] {
+            assert!(check_address(valid).is_ok(), "{}", valid);
+        }
+        for invalid in [
+            "",
+            "10.0.0.256/32",
+            "10.0.0.2/33",
+            "fd00::2/129",
+            "10.0.0.2/",
+            "10.0.0.2/32,",
+            "10.0.0.2/32\nPostUp = id",
+            "wg0.example.com",
+        ] {
+            assert!(check_address(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_interface_stanza() {
+        let options = InterfaceOptions {
+            address: Some("10.0.0.2/32".to_string()),
+            listen_port: Some(51820),
+        };
+        let output = generate_wireguard(&[42u8; 32], Some(&options)).unwrap();
+        let pair = generate_wireguard_keypair(&[42u8; 32]);
+
+        let expected = format!(
+            "[Interface]\n# PublicKey = {}\nPrivateKey = {}\nAddress = 10.0.0.2/32\nListenPort = 51820",
+            pair.public_key, &*pair.private_key
+        );
+        assert_eq!(*output.contents, expected);
+        assert_eq!(output.public_key, pair.public_key);
+
+        let minimal = generate_wireguard(&[42u8; 32], Some(&InterfaceOptions::default())).unwrap();
+        assert_eq!(minimal.contents.lines().count(), 3);
+
+        let invalid = InterfaceOptions {
+            address: Some("10.0.0.2/32\nPostUp = id".to_string()),
+            listen_port: None,
+        };
+        assert!(generate_wireguard(&[42u8; 32], Some(&invalid)).is_err());
+    }
+}
@@ -15,12 +15,22 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 use std::sync::OnceLock;
+use unicode_normalization::UnicodeNormalization;
 
 const WORDLIST_DATA: &str = include_str!("../assets/eff_large_wordlist.txt");
+const EFF_SHORT_1_DATA: &str = include_str!("../assets/eff_short_wordlist_1.txt");
+const EFF_SHORT_2_DATA: &str = include_str!("../assets/eff_short_wordlist_2_0.txt");
 
 #[cfg(test)]
 const EXPECTED_SHA256: &str = "addd35536511597a02fa0a9ff1e5284677b8883b83e986e43f15a3db996b903e";
+#[cfg(test)]
+const EFF_SHORT_1_SHA256: &str = "8f5ca830b8bffb6fe39c9736c024a00a6a6411adb3f83a9be8bfeeb6e067ae69";
+#[cfg(test)]
+const EFF_SHORT_2_SHA256: &str = "22b45c52e0bd0bbf03aa522240b111eb4c7c0c1d86c4e518e1be2a7eb2a625e4";
 
 static WORDLIST: OnceLock<Vec<&'static str>> = OnceLock::new();
 
@@ -45,8 +55,216 @@ pub fn get_wordlist() -> &'static [&'static str] {
     })
 }
 
-pub const fn wordlist_size() -> u16 {
-    7776
+pub const MIN_WORDLIST_LEN: usize = 2;
+pub const MAX_WORDLIST_LEN: usize = 65536;
+pub const MAX_WORD_BYTES: usize = 64;
+
+pub const MAX_SUGGESTION_DISTANCE: usize = 2;
+pub const MAX_SUGGESTIONS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordlistKind {
+    EffLarge,
+    EffShort1,
+    EffShort2,
+    Custom,
+}
+
+impl WordlistKind {
+    pub const BUILTIN: [WordlistKind; 3] = [
+        WordlistKind::EffLarge,
+        WordlistKind::EffShort1,
+        WordlistKind::EffShort2,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WordlistKind::EffLarge => "EFF Large",
+            WordlistKind::EffShort1 => "EFF Short 1",
+            WordlistKind::EffShort2 => "EFF Short 2",
+            WordlistKind::Custom => "Custom (SHA-256 pinned)",
+        }
+    }
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            WordlistKind::EffLarge => "eff-large",
+            WordlistKind::EffShort1 => "eff-short-1",
+            WordlistKind::EffShort2 => "eff-short-2",
+            WordlistKind::Custom => "custom",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::BUILTIN.into_iter().find(|kind| kind.id() == id)
+    }
+
+    fn data(&self) -> Option<&'static str> {
+        match self {
+            WordlistKind::EffLarge => Some(WORDLIST_DATA),
+            WordlistKind::EffShort1 => Some(EFF_SHORT_1_DATA),
+            WordlistKind::EffShort2 => Some(EFF_SHORT_2_DATA),
+            WordlistKind::Custom => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Wordlist {
+    kind: WordlistKind,
+    words: Vec<String>,
+}
+
+impl Wordlist {
+    pub fn eff_large() -> Self {
+        Self {
+            kind: WordlistKind::EffLarge,
+            words: get_wordlist().iter().map(|word| word.to_string()).collect(),
+        }
+    }
+
+    pub fn builtin(kind: WordlistKind) -> Result<Self> {
+        match kind.data() {
+            Some(data) => Self::parse(kind, data),
+            None => anyhow::bail!("{} is not a built-in wordlist", kind.id()),
+        }
+    }
+
+    // The hash covers the file exactly as read, so any edit to the list,
+    // which would silently change every derived mnemonic, is caught.
+    pub fn from_pinned_bytes(data: &[u8], expected_sha256: &str) -> Result<Self> {
+        let expected = expected_sha256.trim().to_ascii_lowercase();
+        if expected.len() != 64 || !expected.bytes().all(|b| b.is_ascii_hexdigit()) {
+            anyhow::bail!("Wordlist SHA-256 must be 64 hex characters");
+        }
+
+        let actual = format!("{:x}", Sha256::digest(data));
+        if actual != expected {
+            anyhow::bail!(
+                "Wordlist SHA-256 mismatch: expected {}, got {}",
+                expected,
+                actual
+            );
+        }
+
+        let text = std::str::from_utf8(data).context("Wordlist is not valid UTF-8")?;
+        Self::parse(WordlistKind::Custom, text)
+    }
+
+    // One word per line, optionally preceded by a diceware index such as
+    // "11111<TAB>abacus". Words are NFC-normalized before the checks.
+    pub fn parse(kind: WordlistKind, text: &str) -> Result<Self> {
+        let mut words = Vec::new();
+        let mut seen = HashSet::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let word = match fields.as_slice() {
+                [] => continue,
+                [word] => *word,
+                [index, word] if index.bytes().all(|b| b.is_ascii_digit()) => *word,
+                _ => anyhow::bail!("Wordlist line {} must hold a single word", number + 1),
+            };
+
+            let word: String = word.nfc().collect();
+            if word.len() > MAX_WORD_BYTES {
+                anyhow::bail!(
+                    "Wordlist line {} is longer than {} bytes",
+                    number + 1,
+                    MAX_WORD_BYTES
+                );
+            }
+            if word.chars().any(|c| c.is_control()) {
+                anyhow::bail!("Wordlist line {} contains control characters", number + 1);
+            }
+            if !seen.insert(word.clone()) {
+                anyhow::bail!("Wordlist contains duplicate word \"{}\"", word);
+            }
+            words.push(word);
+        }
+
+        if !(MIN_WORDLIST_LEN..=MAX_WORDLIST_LEN).contains(&words.len()) {
+            anyhow::bail!(
+                "Wordlist must contain between {} and {} words, got {}",
+                MIN_WORDLIST_LEN,
+                MAX_WORDLIST_LEN,
+                words.len()
+            );
+        }
+
+        Ok(Self { kind, words })
+    }
+
+    pub fn kind(&self) -> WordlistKind {
+        self.kind
+    }
+
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn bits_per_word(&self) -> f64 {
+        (self.len() as f64).log2()
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.iter().any(|w| w == word)
+    }
+
+    // Closest words first; ties keep wordlist order.
+    pub fn suggest(&self, word: &str) -> Vec<&str> {
+        let mut candidates: Vec<(usize, &str)> = self
+            .words
+            .iter()
+            .map(|w| (edit_distance(word, w), w.as_str()))
+            .filter(|&(distance, _)| distance <= MAX_SUGGESTION_DISTANCE)
+            .collect();
+        candidates.sort_by_key(|&(distance, _)| distance);
+
+        candidates
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, w)| w)
+            .collect()
+    }
+}
+
+// Optimal string alignment distance: insertions, deletions, substitutions
+// and adjacent transpositions, the usual slips when copying a word by hand.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+
+    rows[a.len()][b.len()]
 }
 
 #[cfg(test)]
@@ -135,4 +353,145 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_pinned_bytes_match_builtin() {
+        let list = Wordlist::from_pinned_bytes(WORDLIST_DATA.as_bytes(), EXPECTED_SHA256).unwrap();
+        assert_eq!(list.kind(), WordlistKind::Custom);
+        assert_eq!(list.words(), Wordlist::eff_large().words());
+        assert_eq!(list.len(), Wordlist::eff_large().len());
+
+        let uppercase = EXPECTED_SHA256.to_uppercase();
+        assert!(Wordlist::from_pinned_bytes(WORDLIST_DATA.as_bytes(), &uppercase).is_ok());
+    }
+
+    #[test]
+    fn test_pinned_bytes_rejects_hash_mismatch() {
+        let data = b"alpha\nbravo\n";
+        let wrong = "0".repeat(64);
+
+        let err = Wordlist::from_pinned_bytes(data, &wrong).unwrap_err();
+        assert!(err.to_string().contains("mismatch"));
+        assert!(Wordlist::from_pinned_bytes(data, "abc").is_err());
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        let plain = Wordlist::parse(WordlistKind::Custom, "alpha\n\nbravo\ncharlie\n").unwrap();
+        assert_eq!(plain.words(), ["alpha", "bravo", "charlie"]);
+        assert!((plain.bits_per_word() - 3f64.log2()).abs() < 1e-9);
+
+        let diceware = Wordlist::parse(WordlistKind::Custom, "11\talpha\n12 bravo\n").unwrap();
+        assert_eq!(diceware.words(), ["alpha", "bravo"]);
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_lists() {
+        let invalid = [
+            "alpha\nalpha\n",
+            "alpha\n",
+            "alpha beta gamma\ndelta\n",
+            "x1 alpha\nbravo\n",
+            "caf\u{e9}\ncafe\u{301}\n",
+        ];
+        for text in invalid {
+            assert!(
+                Wordlist::parse(WordlistKind::Custom, text).is_err(),
+                "{:?} should be rejected",
+                text
+            );
+        }
+
+        let long_word = "a".repeat(MAX_WORD_BYTES + 1);
+        assert!(Wordlist::parse(WordlistKind::Custom, &format!("{}\nbravo\n", long_word)).is_err());
+    }
+
+    #[test]
+    fn test_eff_short_sha256() {
+        for (data, expected) in [
+            (EFF_SHORT_1_DATA, EFF_SHORT_1_SHA256),
+            (EFF_SHORT_2_DATA, EFF_SHORT_2_SHA256),
+        ] {
+            let mut hasher = Sha256::new();
+            hasher.update(data.as_bytes());
+            let result = format!("{:x}", hasher.finalize());
+
+            assert_eq!(
+                result, expected,
+                "Wordlist SHA-256 mismatch; file may be corrupted"
+            );
+        }
+    }
+
+    #[test]
+    fn test_eff_short_integrity() {
+        let short1 = Wordlist::builtin(WordlistKind::EffShort1).unwrap();
+        let short2 = Wordlist::builtin(WordlistKind::EffShort2).unwrap();
+
+        assert_eq!(short1.len(), 1296);
+        assert_eq!(short1.words()[0], "acid");
+        assert_eq!(short1.words()[1295], "zoom");
+
+        assert_eq!(short2.len(), 1296);
+        assert_eq!(short2.words()[0], "aardvark");
+        assert_eq!(short2.words()[1295], "zucchini");
+
+        for list in [&short1, &short2] {
+            for (i, word) in list.words().iter().enumerate() {
+                assert!(
+                    word.chars().all(|c| c.is_ascii_lowercase() || c == '-'),
+                    "Word at index {} (\"{}\") contains invalid characters",
+                    i,
+                    word
+                );
+            }
+        }
+
+        // The second short list is designed so that three letters identify a word
+        let prefixes: HashSet<&str> = short2.words().iter().map(|w| &w[..3]).collect();
+        assert_eq!(prefixes.len(), short2.len());
+    }
+
+    #[test]
+    fn test_builtin_selector() {
+        for kind in WordlistKind::BUILTIN {
+            assert_eq!(WordlistKind::from_id(kind.id()), Some(kind));
+            assert_eq!(Wordlist::builtin(kind).unwrap().kind(), kind);
+        }
+        assert_eq!(WordlistKind::from_id("custom"), None);
+        assert!(Wordlist::builtin(WordlistKind::Custom).is_err());
+
+        assert_eq!(
+            Wordlist::builtin(WordlistKind::EffLarge).unwrap().words(),
+            Wordlist::eff_large().words()
+        );
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("abdomen", "abodmen"), 1);
+        assert_eq!(edit_distance("abacus", "abacu"), 1);
+        assert_eq!(edit_distance("café", "cafe"), 1);
+    }
+
+    #[test]
+    fn test_suggest() {
+        let wordlist = Wordlist::eff_large();
+        assert!(wordlist.contains("abacus"));
+        assert!(!wordlist.contains("abacsu"));
+
+        let suggestions = wordlist.suggest("abacsu");
+        assert_eq!(suggestions[0], "abacus");
+        assert!(suggestions.len() <= MAX_SUGGESTIONS);
+        assert!(
+            suggestions
+                .iter()
+                .all(|w| edit_distance("abacsu", w) <= MAX_SUGGESTION_DISTANCE)
+        );
+
+        assert!(wordlist.suggest("qqqqqqqqqq").is_empty());
+    }
 }